@@ -2,7 +2,7 @@ use serde::{Serialize, Deserialize};
 use crate::forces;
 use crate::update;
 
-#[derive(Serialize,Deserialize,Debug,Clone,Copy)]
+#[derive(Serialize,Deserialize,Debug,Clone,Copy,Default)]
 pub struct Pos {
   pub x: f64,
   pub y: f64
@@ -29,13 +29,111 @@ pub struct Cell {
   pub initial_pos: Pos,
   pub strain: Option<Pos>,
   pub tensor_stress: Option<Stress>,
+  #[serde(default = "default_mass")]
+  pub mass: f64,
+  #[serde(default)]
+  pub vel: Pos,
+  #[serde(default)]
+  pub displace: forces::DispFunc,
+  #[serde(default)]
+  pub links: Vec<ForceLink>,
+  #[serde(default)]
+  pub broken: usize, // bonds of this cell that have ruptured
+  #[serde(default)]
+  pub angles: Vec<AngleLink>,
+  #[serde(default)]
+  pub parent: Option<usize>, // cell this one divided off from
+  #[serde(default = "default_nutrient")]
+  pub nutrient: f64, // nutrient concentration where the cell sits, in units of the supply
+  #[serde(default)]
+  pub species: usize, // index into the species table of the settings
+  #[serde(default)]
+  pub orientation: f64, // direction the cell propels itself in, counterclockwise from +x
+  #[serde(default)]
+  pub tether: Option<Tether>, // spring to the substrate, none if never attached or detached
+  #[serde(default)]
+  pub detached: Option<f64>, // time the tether let go
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+// A spring bond, kept by both of its cells. parties is (this cell, other cell) and
+// value is the spring constant.
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ForceLink {
-  parties: (usize, usize),
-  value: f64,
-  relax_length: f64
+  pub parties: (usize, usize),
+  pub value: f64,
+  pub relax_length: f64,
+  #[serde(default)]
+  pub kind: BondType
+}
+
+// A bending spring between two bonds of a cell, kept by the cell at the vertex. parties is
+// (this cell, first arm, second arm), the angle is measured counterclockwise from the first
+// arm and value is the bending stiffness.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AngleLink {
+  pub parties: (usize, usize, usize),
+  pub value: f64,
+  pub rest_angle: f64
+}
+
+// A spring of anchor_k from a cell to a fixed point of the substrate
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct Tether {
+  pub point: Pos,
+  pub relax_length: f64
+}
+
+// Where free cells are tethered to the substrate
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Anchoring {
+  None,
+  Initial, // to where they start, at rest length 0
+  Substrate, // to the point of the line y = substrate_y below where they start, at rest
+}
+
+pub fn anchoring_enum(name: &str) -> Option<Anchoring> {
+  match name {
+    "none" => Some(Anchoring::None),
+    "initial" => Some(Anchoring::Initial),
+    "substrate" => Some(Anchoring::Substrate),
+    _ => None
+  }
+}
+
+// How a bond responds to the rate it is stretched at
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum BondType {
+  #[default]
+  Elastic,
+  KelvinVoigt, // dashpot of kv_viscosity alongside the spring
+  Maxwell, // dashpot of maxwell_viscosity after the spring, the rest length flows
+}
+
+pub fn bond_type_enum(name: &str) -> Option<BondType> {
+  match name {
+    "elastic" => Some(BondType::Elastic),
+    "kelvin_voigt" => Some(BondType::KelvinVoigt),
+    "maxwell" => Some(BondType::Maxwell),
+    _ => None
+  }
+}
+
+// How the area of free cells grows
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Growth {
+  None,
+  Exponential, // at growth_rate
+  Monod, // at growth_rate slowed by the nutrient left, which the colony uses up as it grows,
+         // or by the nutrient field where there is one
+}
+
+pub fn growth_enum(name: &str) -> Option<Growth> {
+  match name {
+    "none" => Some(Growth::None),
+    "exponential" => Some(Growth::Exponential),
+    "monod" => Some(Growth::Monod),
+    _ => None
+  }
 }
 
 impl Cell {
@@ -55,17 +153,53 @@ impl Cell {
       force: forces::ForceFunc::None,
       initial_pos: Pos { x, y },
       strain: None,
-      tensor_stress: None
+      tensor_stress: None,
+      mass: 1.0,
+      vel: Pos { x: 0.0, y: 0.0 },
+      displace: forces::DispFunc::None,
+      links: vec![],
+      broken: 0,
+      angles: vec![],
+      parent: None,
+      nutrient: 1.0,
+      species: 0,
+      orientation: 0.0,
+      tether: None,
+      detached: None
     }
   }
+
+  // Fixed and displacement driven cells don't grow or divide
+  pub fn grows(&self) -> bool {
+    !self.fixed && self.displace == forces::DispFunc::None
+  }
+}
+
+fn default_mass() -> f64 {
+  1.0
+}
+
+fn default_nutrient() -> f64 {
+  1.0
 }
 
 impl ForceLink {
-  pub fn new(p1: usize, p2: usize, val: f64, relax: f64) -> ForceLink {
+  pub fn new(p1: usize, p2: usize, val: f64, relax: f64, kind: BondType) -> ForceLink {
     ForceLink {
       parties: (p1, p2),
       value: val,
-      relax_length: relax
+      relax_length: relax,
+      kind
+    }
+  }
+}
+
+impl AngleLink {
+  pub fn new(p1: usize, p2: usize, p3: usize, val: f64, rest: f64) -> AngleLink {
+    AngleLink {
+      parties: (p1, p2, p3),
+      value: val,
+      rest_angle: rest
     }
   }
 }
@@ -0,0 +1,130 @@
+use serde::{Serialize, Deserialize};
+use crate::{cell, settings, simulation};
+
+use super::RustfilmError;
+
+// Background flow of the fluid around the film
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Flow {
+  None,
+  Uniform, // flow_speed along x everywhere
+  Poiseuille, // along x through the channel between wall_ymin and wall_ymax, flow_speed at the middle
+  Table, // interpolated from flow_table
+}
+
+pub fn flow_enum(name: &str) -> Option<Flow> {
+  match name {
+    "none" => Some(Flow::None),
+    "uniform" => Some(Flow::Uniform),
+    "poiseuille" => Some(Flow::Poiseuille),
+    "table" => Some(Flow::Table),
+    _ => None
+  }
+}
+
+// Flow velocities on a rectangular lattice, vel row by row from the lowest y
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct FlowTable {
+  pub xs: Vec<f64>,
+  pub ys: Vec<f64>,
+  pub vel: Vec<cell::Pos>,
+}
+
+impl FlowTable {
+  // Reads rows of "x y vx vy", separated by whitespace or commas, that cover every point of
+  // a lattice once in any order. Blank lines and lines starting with # are skipped.
+  pub fn read(name: &str) -> Result<FlowTable, RustfilmError> {
+    let contents = match std::fs::read_to_string(name) {
+      Ok(contents) => contents,
+      Err(_e) => return Err(RustfilmError{error: "Failed to read flow table".to_string()})
+    };
+
+    let mut rows: Vec<[f64; 4]> = vec![];
+    for line in contents.lines() {
+      let line = line.trim();
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+      let row: Result<Vec<f64>, _> = line.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|p| !p.is_empty())
+        .map(|p| p.parse::<f64>())
+        .collect();
+      match row {
+        Ok(row) if row.len() == 4 => {
+          if row.iter().any(|v| !v.is_finite()) {
+            return Err(RustfilmError{error: "flow table values must be finite".to_string()});
+          }
+          rows.push([row[0], row[1], row[2], row[3]]);
+        },
+        _ => return Err(RustfilmError{error: "flow table failed to parse".to_string()})
+      }
+    }
+
+    let axis = |k: usize| {
+      let mut values: Vec<f64> = rows.iter().map(|row| row[k]).collect();
+      values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+      values.dedup();
+      values
+    };
+    let xs = axis(0);
+    let ys = axis(1);
+    if xs.len() < 2 || ys.len() < 2 || rows.len() != xs.len() * ys.len() {
+      return Err(RustfilmError{error: "flow table must fill a lattice of at least 2 by 2 points".to_string()});
+    }
+
+    let mut vel: Vec<Option<cell::Pos>> = vec![None; rows.len()];
+    for row in &rows {
+      let col = xs.iter().position(|x| *x == row[0]).unwrap();
+      let line = ys.iter().position(|y| *y == row[1]).unwrap();
+      vel[line * xs.len() + col] = Some(cell::Pos{x: row[2], y: row[3]});
+    }
+    match vel.into_iter().collect::<Option<Vec<_>>>() {
+      Some(vel) => Ok(FlowTable { xs, ys, vel }),
+      None => Err(RustfilmError{error: "flow table has a point more than once".to_string()})
+    }
+  }
+
+  // Bilinear interpolation, holding the edge values past the lattice
+  fn at(&self, x: f64, y: f64) -> cell::Pos {
+    let locate = |values: &[f64], v: f64| {
+      let upper = values.partition_point(|p| *p <= v).clamp(1, values.len() - 1);
+      let weight = ((v - values[upper - 1]) / (values[upper] - values[upper - 1])).clamp(0.0, 1.0);
+      (upper - 1, weight)
+    };
+    let (col, wx) = locate(&self.xs, x);
+    let (line, wy) = locate(&self.ys, y);
+    let corner = |dl: usize, dc: usize| self.vel[(line + dl) * self.xs.len() + col + dc];
+    let mix = |a: f64, b: f64, c: f64, d: f64| {
+      (1.0 - wy) * ((1.0 - wx) * a + wx * b) + wy * ((1.0 - wx) * c + wx * d)
+    };
+    let (a, b, c, d) = (corner(0, 0), corner(0, 1), corner(1, 0), corner(1, 1));
+    cell::Pos{x: mix(a.x, b.x, c.x, d.x), y: mix(a.y, b.y, c.y, d.y)}
+  }
+}
+
+// Velocity of the flow at pos. Periodic axes wrap into the unit box.
+pub fn velocity(pos: &cell::Pos, settings: &settings::Settings) -> cell::Pos {
+  match settings.flow {
+    Flow::None => cell::Pos{x: 0.0, y: 0.0},
+    Flow::Uniform => cell::Pos{x: settings.flow_speed, y: 0.0},
+    Flow::Poiseuille => {
+      let width = settings.wall_ymax - settings.wall_ymin;
+      let across = (pos.y - settings.wall_ymin) / width;
+      let speed = if (0.0..=1.0).contains(&across) { 4.0 * settings.flow_speed * across * (1.0 - across) } else { 0.0 };
+      cell::Pos{x: speed, y: 0.0}
+    },
+    Flow::Table => {
+      let x = if settings.periodic_x { pos.x.rem_euclid(simulation::PERIOD) } else { pos.x };
+      let y = if settings.periodic_y { pos.y.rem_euclid(simulation::PERIOD) } else { pos.y };
+      settings.flow_table.at(x, y)
+    }
+  }
+}
+
+// Stokes drag coefficient of the flow on a cell, in proportion to its radius
+pub fn drag(c: &cell::Cell, settings: &settings::Settings) -> f64 {
+  if settings.flow == Flow::None {
+    return 0.0;
+  }
+  settings.flow_drag * c.radius
+}
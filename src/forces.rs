@@ -6,6 +6,99 @@ pub enum ForceFunc {
   None,
   Constrained,
   Sine,
+  Step,
+  Ramp,
+  Square,
+  Triangle,
+  Chirp,
+  MultiSine,
+  Table,
+  Shear,
+  Compress,
+  Biaxial,
+  Equibiaxial,
+}
+
+// Prescribed x displacement from initial_pos for displacement driven cells
+#[derive(Serialize, Deserialize,Debug,PartialEq,Eq,Copy,Clone,Default)]
+pub enum DispFunc {
+  #[default]
+  None,
+  Ramp,
+  Sine,
+  Table,
+}
+
+// Shape of the walls that confine every free cell
+#[derive(Serialize, Deserialize,Debug,PartialEq,Eq,Copy,Clone)]
+pub enum Wall {
+  None,
+  Channel, // y between wall_ymin and wall_ymax
+  Box, // the channel with x between wall_xmin and wall_xmax as well
+  Circle, // within well_radius of (well_x, well_y)
+  Polygon, // inside the wall_polygon vertices
+}
+
+// How a wall pushes on a cell by how far inside of it the cell is
+#[derive(Serialize, Deserialize,Debug,PartialEq,Eq,Copy,Clone)]
+pub enum WallPotential {
+  Harmonic, // restraint_k times how far the cell is past the wall
+  LennardJones, // lj_epsilon and lj_sigma, cut off at 2.5 lj_sigma
+  Wca, // Lennard-Jones cut off at its minimum, so it only repels
+}
+
+pub fn wall_enum(name: &str) -> Option<Wall> {
+  match name {
+    "none" => Some(Wall::None),
+    "channel" => Some(Wall::Channel),
+    "box" => Some(Wall::Box),
+    "circle" => Some(Wall::Circle),
+    "polygon" => Some(Wall::Polygon),
+    _ => None
+  }
+}
+
+pub fn potential_enum(name: &str) -> Option<WallPotential> {
+  match name {
+    "harmonic" => Some(WallPotential::Harmonic),
+    "lj" => Some(WallPotential::LennardJones),
+    "wca" => Some(WallPotential::Wca),
+    _ => None
+  }
+}
+
+// How the periodic images above and below the grid slide along x, shearing the whole grid
+#[derive(Serialize, Deserialize,Debug,PartialEq,Eq,Copy,Clone)]
+pub enum LeesEdwards {
+  None,
+  Steady, // strain grows at shear_rate
+  Oscillatory, // strain of amplitude shear_strain at the angular frequency of the sine drive
+}
+
+pub fn lees_edwards_enum(name: &str) -> Option<LeesEdwards> {
+  match name {
+    "none" => Some(LeesEdwards::None),
+    "steady" => Some(LeesEdwards::Steady),
+    "oscillatory" => Some(LeesEdwards::Oscillatory),
+    _ => None
+  }
+}
+
+// Shear strain the Lees-Edwards images have been moved through at time t
+pub fn applied_shear(t: f64, s: &settings::Settings) -> f64 {
+  match s.lees_edwards {
+    LeesEdwards::None => 0.0,
+    LeesEdwards::Steady => s.shear_rate * t,
+    LeesEdwards::Oscillatory => s.shear_strain * (s.sineomega * t).sin(),
+  }
+}
+
+pub fn applied_shear_rate(t: f64, s: &settings::Settings) -> f64 {
+  match s.lees_edwards {
+    LeesEdwards::None => 0.0,
+    LeesEdwards::Steady => s.shear_rate,
+    LeesEdwards::Oscillatory => s.shear_strain * s.sineomega * (s.sineomega * t).cos(),
+  }
 }
 
 pub fn force_func(e: &ForceFunc) ->
@@ -20,6 +113,17 @@ pub fn force_func(e: &ForceFunc) ->
     ForceFunc::None => force_none,
     ForceFunc::Constrained => force_constrained,
     ForceFunc::Sine => force_sine,
+    ForceFunc::Step => force_step,
+    ForceFunc::Ramp => force_ramp,
+    ForceFunc::Square => force_square,
+    ForceFunc::Triangle => force_triangle,
+    ForceFunc::Chirp => force_chirp,
+    ForceFunc::MultiSine => force_multisine,
+    ForceFunc::Table => force_table,
+    ForceFunc::Shear => force_shear,
+    ForceFunc::Compress => force_compress,
+    ForceFunc::Biaxial => force_biaxial,
+    ForceFunc::Equibiaxial => force_equibiaxial,
   }
 }
 
@@ -35,10 +139,9 @@ pub fn force_none(
 pub fn force_constrained(
   t: f64,
   c: &mut cell::Cell,
-  i: usize,
+  _i: usize,
   s: &settings::Settings
 ) -> cell::Pos {
-  let constraint = linear_restraint(t, c, i, s);
   let mut force_x = 0.0;
 
   c.fixed = true;
@@ -49,55 +152,278 @@ pub fn force_constrained(
   }
 
   cell::Pos{
-    x: constraint.x + force_x,
-    y: constraint.y
+    x: force_x,
+    y: 0.0
   }
 }
 
 pub fn force_sine(
   t: f64,
-  c: &mut cell::Cell,
-  i: usize,
+  _c: &mut cell::Cell,
+  _i: usize,
   s: &settings::Settings
 ) -> cell::Pos {
-  let constraint = linear_restraint(t, c, i, s);
   cell::Pos{
-    x: constraint.x + s.sineamp * (s.sineomega * t).sin(),
-    y: constraint.y
+    x: s.sineamp * (s.sineomega * t).sin(),
+    y: 0.0
   }
 }
 
-pub fn linear_restraint(
-  _t: f64,
-  c: &mut cell::Cell,
+// Constant extforce_x switched on at load_start, for creep tests
+pub fn force_step(
+  t: f64,
+  _c: &mut cell::Cell,
   _i: usize,
   s: &settings::Settings
 ) -> cell::Pos {
-  let mut y_force = 0.0;
-  if c.pos.y > 1.0 {
-    y_force = s.restraint_k * (1.0 - c.pos.y);
-  } else if c.pos.y < 0.0 {
-    y_force = s.restraint_k * -c.pos.y;
+  let force_x = if t >= s.load_start { s.extforce_x } else { 0.0 };
+  cell::Pos{
+    x: force_x,
+    y: 0.0
   }
+}
 
-  cell::Pos {
-    x: 0.0,
-    y: y_force
+// Rises linearly from load_start to extforce_x over ramp_time, then holds
+pub fn force_ramp(
+  t: f64,
+  _c: &mut cell::Cell,
+  _i: usize,
+  s: &settings::Settings
+) -> cell::Pos {
+  let fraction = ((t - s.load_start) / s.ramp_time).clamp(0.0, 1.0);
+  cell::Pos{
+    x: s.extforce_x * fraction,
+    y: 0.0
   }
 }
 
-pub fn lj_restraint(
-  _t: f64,
-  c: &mut cell::Cell,
+// Square wave with the amplitude and angular frequency of the sine drive
+pub fn force_square(
+  t: f64,
+  _c: &mut cell::Cell,
   _i: usize,
   s: &settings::Settings
 ) -> cell::Pos {
-  let mut y_force = 0.0;
+  let phase = (s.sineomega * t).sin();
+  let force_x = if phase >= 0.0 { s.sineamp } else { -s.sineamp };
+  cell::Pos{
+    x: force_x,
+    y: 0.0
+  }
+}
+
+// Triangle wave with the amplitude and angular frequency of the sine drive
+pub fn force_triangle(
+  t: f64,
+  _c: &mut cell::Cell,
+  _i: usize,
+  s: &settings::Settings
+) -> cell::Pos {
+  let force_x = s.sineamp * 2.0 / std::f64::consts::PI * (s.sineomega * t).sin().asin();
+  cell::Pos{
+    x: force_x,
+    y: 0.0
+  }
+}
 
+// Sine whose angular frequency sweeps linearly from sineomega to chirp_omega over del_t
+pub fn force_chirp(
+  t: f64,
+  _c: &mut cell::Cell,
+  _i: usize,
+  s: &settings::Settings
+) -> cell::Pos {
+  let phase = s.sineomega * t + (s.chirp_omega - s.sineomega) * t * t / (2.0 * s.del_t);
+  cell::Pos{
+    x: s.sineamp * phase.sin(),
+    y: 0.0
+  }
+}
+
+// Sum of the (amplitude, angular frequency, phase) sines in multisine
+pub fn force_multisine(
+  t: f64,
+  _c: &mut cell::Cell,
+  _i: usize,
+  s: &settings::Settings
+) -> cell::Pos {
+  let force_x: f64 = s.multisine.iter().map(|(amp, omega, phase)| amp * (omega * t + phase).sin()).sum();
+  cell::Pos{
+    x: force_x,
+    y: 0.0
+  }
+}
+
+// Linear interpolation of the (time, force) rows of force_table, held flat past either end
+pub fn force_table(
+  t: f64,
+  _c: &mut cell::Cell,
+  _i: usize,
+  s: &settings::Settings
+) -> cell::Pos {
+  cell::Pos{
+    x: interpolate(&s.force_table, t),
+    y: 0.0
+  }
+}
+
+// Oscillatory shear of the top plate along x, with the amplitude and angular frequency of the sine drive
+pub fn force_shear(
+  t: f64,
+  _c: &mut cell::Cell,
+  _i: usize,
+  s: &settings::Settings
+) -> cell::Pos {
+  cell::Pos{
+    x: s.sineamp * (s.sineomega * t).sin(),
+    y: 0.0
+  }
+}
+
+// Pushes the top plate down with extforce_y from load_start on
+pub fn force_compress(
+  t: f64,
+  _c: &mut cell::Cell,
+  _i: usize,
+  s: &settings::Settings
+) -> cell::Pos {
+  cell::Pos{
+    x: 0.0,
+    y: if t >= s.load_start { -s.extforce_y } else { 0.0 }
+  }
+}
+
+// Pulls each edge of the major grid outwards, extforce_x on the left and right columns and
+// extforce_y on the bottom and top rows, from load_start on. Corners are pulled both ways.
+pub fn force_biaxial(
+  t: f64,
+  _c: &mut cell::Cell,
+  i: usize,
+  s: &settings::Settings
+) -> cell::Pos {
+  stretch(t, i, s, s.extforce_x, s.extforce_y)
+}
+
+// Biaxial stretch with extforce_x along both axes
+pub fn force_equibiaxial(
+  t: f64,
+  _c: &mut cell::Cell,
+  i: usize,
+  s: &settings::Settings
+) -> cell::Pos {
+  stretch(t, i, s, s.extforce_x, s.extforce_x)
+}
+
+fn stretch(t: f64, i: usize, s: &settings::Settings, force_x: f64, force_y: f64) -> cell::Pos {
+  let mut force = cell::Pos{x: 0.0, y: 0.0};
+  if t < s.load_start || i >= s.nrows * s.nrows {
+    return force;
+  }
+
+  // Major cells are stored row by row
+  let (row, col) = (i / s.nrows, i % s.nrows);
+  if col == 0 {
+    force.x -= force_x;
+  } else if col == s.nrows - 1 {
+    force.x += force_x;
+  }
+  if row == 0 {
+    force.y -= force_y;
+  } else if row == s.nrows - 1 {
+    force.y += force_y;
+  }
+  force
+}
+
+// Piecewise linear interpolation of (time, value) rows sorted by time
+pub fn interpolate(table: &[(f64, f64)], t: f64) -> f64 {
+  let next = table.iter().position(|(time, _)| *time > t);
+  match next {
+    None => table.last().map_or(0.0, |(_, value)| *value),
+    Some(0) => table[0].1,
+    Some(next) => {
+      let (t0, v0) = table[next - 1];
+      let (t1, v1) = table[next];
+      v0 + (v1 - v0) * (t - t0) / (t1 - t0)
+    }
+  }
+}
+
+pub fn disp_func(e: &DispFunc) -> fn(f64, &settings::Settings) -> f64 {
+  match e {
+    DispFunc::None => disp_none,
+    DispFunc::Ramp => disp_ramp,
+    DispFunc::Sine => disp_sine,
+    DispFunc::Table => disp_table,
+  }
+}
+
+// Central difference of the displacement, so every path has a velocity without a second function
+pub fn disp_velocity(e: &DispFunc, t: f64, s: &settings::Settings) -> f64 {
+  let disp_func = disp_func(e);
+  let h = 1e-6;
+  (disp_func(t + h, s) - disp_func(t - h, s)) / (2.0 * h)
+}
+
+pub fn disp_none(_t: f64, _s: &settings::Settings) -> f64 {
+  0.0
+}
+
+// Moves at disp_rate from load_start on
+pub fn disp_ramp(t: f64, s: &settings::Settings) -> f64 {
+  s.disp_rate * (t - s.load_start).max(0.0)
+}
+
+pub fn disp_sine(t: f64, s: &settings::Settings) -> f64 {
+  s.disp_amp * (s.sineomega * t).sin()
+}
+
+pub fn disp_table(t: f64, s: &settings::Settings) -> f64 {
+  interpolate(&s.disp_table, t)
+}
+
+// Self-propulsion of a cell along its orientation
+pub fn active_force(c: &cell::Cell, s: &settings::Settings) -> cell::Pos {
+  cell::Pos{x: s.active_force * c.orientation.cos(), y: s.active_force * c.orientation.sin()}
+}
+
+// Pull of a cell's tether back toward its point on the substrate
+pub fn tether_force(c: &cell::Cell, s: &settings::Settings) -> cell::Pos {
+  match c.tether {
+    Some(tether) => {
+      let offset = c.pos.sub(&tether.point);
+      let dist = offset.norm();
+      if dist == 0.0 {
+        return cell::Pos{x: 0.0, y: 0.0};
+      }
+      let pull = -s.anchor_k * (dist - tether.relax_length) / dist;
+      cell::Pos{x: pull * offset.x, y: pull * offset.y}
+    },
+    None => cell::Pos{x: 0.0, y: 0.0}
+  }
+}
+
+// Force of the walls on a cell, pushing it back inside. Without walls, cells driven by a
+// force are still held between y = 0 and 1.
+pub fn wall_force(c: &cell::Cell, s: &settings::Settings) -> cell::Pos {
+  if s.wall == Wall::None && c.force != ForceFunc::None {
+    return linear_restraint(c, s);
+  }
+  let mut force = cell::Pos{x: 0.0, y: 0.0};
+  for (dist, normal) in wall_contacts(&c.pos, s) {
+    let push = wall_push(dist, s);
+    force.x += push * normal.x;
+    force.y += push * normal.y;
+  }
+  force
+}
+
+fn linear_restraint(c: &cell::Cell, s: &settings::Settings) -> cell::Pos {
+  let mut y_force = 0.0;
   if c.pos.y > 1.0 {
-    y_force = -4.0 * s.lj_epsilon * (12.0 * s.lj_sigma.powi(12) * (c.pos.y - 1.0).powi(-13) - 6.0 * s.lj_sigma.powi(6) * (c.pos.y - 1.0).powi(-7));
+    y_force = s.restraint_k * (1.0 - c.pos.y);
   } else if c.pos.y < 0.0 {
-    y_force = 4.0 * s.lj_epsilon * (12.0 * s.lj_sigma.powi(12) * c.pos.y.powi(-13) - 6.0 * s.lj_sigma.powi(6) * c.pos.y.powi(-7));
+    y_force = s.restraint_k * -c.pos.y;
   }
 
   cell::Pos {
@@ -105,3 +431,90 @@ pub fn lj_restraint(
     y: y_force
   }
 }
+
+// Distance inside of each wall a cell at pos feels, negative past the wall, and the
+// direction pointing inside
+fn wall_contacts(pos: &cell::Pos, s: &settings::Settings) -> Vec<(f64, cell::Pos)> {
+  let channel = vec![
+    (pos.y - s.wall_ymin, cell::Pos{x: 0.0, y: 1.0}),
+    (s.wall_ymax - pos.y, cell::Pos{x: 0.0, y: -1.0})
+  ];
+
+  match s.wall {
+    Wall::None => vec![],
+    Wall::Channel => channel,
+    Wall::Box => {
+      let mut walls = channel;
+      walls.push((pos.x - s.wall_xmin, cell::Pos{x: 1.0, y: 0.0}));
+      walls.push((s.wall_xmax - pos.x, cell::Pos{x: -1.0, y: 0.0}));
+      walls
+    },
+    Wall::Circle => {
+      let from_center = cell::Pos{x: pos.x - s.well_x, y: pos.y - s.well_y};
+      let r = from_center.norm();
+      if r == 0.0 {
+        return vec![];
+      }
+      vec![(s.well_radius - r, cell::Pos{x: -from_center.x / r, y: -from_center.y / r})]
+    },
+    Wall::Polygon => polygon_contact(pos, &s.wall_polygon).into_iter().collect(),
+  }
+}
+
+// The nearest point on the edges of the polygon, as a wall contact
+fn polygon_contact(pos: &cell::Pos, vertices: &[(f64, f64)]) -> Option<(f64, cell::Pos)> {
+  let mut nearest: Option<(f64, cell::Pos)> = None; // distance, and offset from the edge to pos
+  let mut inside = false;
+
+  for n in 0..vertices.len() {
+    let (ax, ay) = vertices[n];
+    let (bx, by) = vertices[(n + 1) % vertices.len()];
+
+    // Counting the edges a ray in +x crosses tells whether pos is inside
+    if (ay > pos.y) != (by > pos.y) && pos.x < ax + (pos.y - ay) * (bx - ax) / (by - ay) {
+      inside = !inside;
+    }
+
+    let (ex, ey) = (bx - ax, by - ay);
+    let length_sq = ex * ex + ey * ey;
+    let along = if length_sq == 0.0 { 0.0 } else { (((pos.x - ax) * ex + (pos.y - ay) * ey) / length_sq).clamp(0.0, 1.0) };
+    let offset = cell::Pos{x: pos.x - (ax + along * ex), y: pos.y - (ay + along * ey)};
+    let dist = offset.norm();
+    let closer = match nearest {
+      Some((nearest_dist, _)) => dist < nearest_dist,
+      None => true
+    };
+    if closer {
+      nearest = Some((dist, offset));
+    }
+  }
+
+  let (dist, offset) = nearest?;
+  if dist == 0.0 {
+    return None;
+  }
+  if inside {
+    Some((dist, cell::Pos{x: offset.x / dist, y: offset.y / dist}))
+  } else {
+    Some((-dist, cell::Pos{x: -offset.x / dist, y: -offset.y / dist}))
+  }
+}
+
+// How hard a wall pushes a cell dist inside of it
+fn wall_push(dist: f64, s: &settings::Settings) -> f64 {
+  match s.wall_potential {
+    WallPotential::Harmonic => if dist < 0.0 { -s.restraint_k * dist } else { 0.0 },
+    WallPotential::LennardJones => lj_push(dist, 2.5 * s.lj_sigma, s),
+    WallPotential::Wca => lj_push(dist, 2f64.powf(1.0 / 6.0) * s.lj_sigma, s),
+  }
+}
+
+// Lennard-Jones push of a wall, cut off at cutoff. Cells nearer than half of lj_sigma, or past
+// the wall, are pushed as hard as at half of lj_sigma.
+fn lj_push(dist: f64, cutoff: f64, s: &settings::Settings) -> f64 {
+  if dist >= cutoff {
+    return 0.0;
+  }
+  let dist = dist.max(0.5 * s.lj_sigma);
+  4.0 * s.lj_epsilon * (12.0 * s.lj_sigma.powi(12) * dist.powi(-13) - 6.0 * s.lj_sigma.powi(6) * dist.powi(-7))
+}
@@ -1,6 +1,12 @@
 use crate::settings;
 use crate::cell;
 use crate::update;
+use crate::noise;
+use crate::simulation;
+use crate::nutrient;
+use crate::species;
+use crate::indenter;
+use serde::{Serialize, Deserialize};
 use float_cmp::approx_eq;
 use std::f64;
 
@@ -8,6 +14,23 @@ use super::RustfilmError;
 
 pub type Hook = fn(usize, usize, &mut cell::Cell, &settings::Settings) -> ();
 
+// How spring constants vary over the grid
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Copy, Clone)]
+pub enum BondProfile {
+  Uniform,
+  Core, // bonds within core_radius of the center are profile_k times stiffer
+  Graded, // stiffness rises linearly in x up to profile_k times spring_k
+}
+
+pub fn profile_enum(name: &str) -> Option<BondProfile> {
+  match name {
+    "uniform" => Some(BondProfile::Uniform),
+    "core" => Some(BondProfile::Core),
+    "graded" => Some(BondProfile::Graded),
+    _ => None
+  }
+}
+
 pub fn generate_offsetgrid(
     settings: &mut settings::Settings,
     size: f64,
@@ -23,7 +46,20 @@ pub fn generate_offsetgrid(
 
     let taken_space = 2.0 * size * (settings.nrows as f64); // Amount of space the cells themselves take up
 
-    let gap_space = (1.0 - taken_space) / (settings.nrows as f64 - 1.0); // Space left over to space out the cells
+    let periodic = settings.periodic_x || settings.periodic_y;
+    let gap_space = if periodic {
+      // The last row and column are spaced from the first ones across the wrap too
+      if settings.nrows < 3 {
+        return Err(RustfilmError { error: "Periodic grids need at least 3 rows".to_string() });
+      }
+      let gap_space = (simulation::PERIOD - taken_space) / (settings.nrows as f64);
+      if gap_space < 2.0 * size {
+        return Err(RustfilmError { error: "Space needed exceeds limits".to_string() });
+      }
+      gap_space
+    } else {
+      (1.0 - taken_space) / (settings.nrows as f64 - 1.0) // Space left over to space out the cells
+    };
 
     // Generate the major rows
     let mut xpos = size;
@@ -44,11 +80,15 @@ pub fn generate_offsetgrid(
 
     xpos = 2.0 * size + gap_space / 2.0;
     ypos = 2.0 * size + gap_space / 2.0;
-    for i in 0..settings.nrows-1 {
-      for j in 0..settings.nrows-1 {
+    // Periodic axes get an extra minor row or column, between the last major one and the wrap
+    let minor_rows = settings.nrows - 1 + settings.periodic_y as usize;
+    let minor_cols = settings.nrows - 1 + settings.periodic_x as usize;
+    for i in 0..minor_rows {
+      for j in 0..minor_cols {
         grid.push(cell::Cell::new(xpos, ypos, size));
         if let Some(hook) = minor_hook {
-          hook(i, j, &mut grid[settings.nrows*settings.nrows + i * settings.nrows + j], settings);
+          let last = grid.len() - 1;
+          hook(i, j, &mut grid[last], settings);
         }
         xpos += iter_space;
       }
@@ -61,6 +101,11 @@ pub fn generate_offsetgrid(
 
     settings.spring_relax_close = small_space;
     settings.spring_relax_far = big_space;
+    if let Some(error) = settings.check_capture() {
+      return Err(error);
+    }
+
+    species::assign(&mut grid, settings);
 
     for ind in 0..grid.len() {
       let cell = &grid[ind];
@@ -71,7 +116,7 @@ pub fn generate_offsetgrid(
         if ind == index {
           continue;
         }
-        let mydist = cell.pos.sub(&other.pos).norm();
+        let mydist = simulation::separation(&cell.pos, &other.pos, 0.0, settings).norm();
         if approx_eq!(f64, mydist, big_space, ulps = 5, epsilon = 0.00005) {
           neighbor_far.push(index);
         } else if approx_eq!(f64, mydist, small_space, ulps = 5, epsilon = 0.00005) {
@@ -84,11 +129,146 @@ pub fn generate_offsetgrid(
       cell.neighbor_far = neighbor_far.clone();
     }
 
+    link_neighbors(&mut grid, settings);
+    vary_stiffness(&mut grid, settings);
+    if settings.bend_k > 0.0 {
+      link_angles(&mut grid, settings);
+    }
+
     for mut cell in grid.iter_mut() {
+      let scale = species::of(settings, cell).radius;
+      cell.radius *= scale;
+      cell.mass = settings.mass * scale * scale;
       if cell.update != update::UpdateFunc::None {
         update::update(&mut cell);
       }
     }
 
+    // Cells divide at twice their generated area unless told otherwise, and Monod growth uses
+    // nutrient in proportion to the starting area
+    if settings.division_radius == 0.0 {
+      settings.division_radius = f64::consts::SQRT_2 * size;
+    }
+    settings.start_area = grid.iter().filter(|c| c.grows()).map(|c| f64::consts::PI * c.radius * c.radius).sum();
+    if let Some(field) = nutrient::Field::solve(&grid, settings) {
+      field.apply(&mut grid, settings);
+    }
+    if settings.active_force > 0.0 {
+      orient(&mut grid, settings);
+    }
+    if settings.anchoring != cell::Anchoring::None {
+      anchor(&mut grid, settings);
+    }
+    // An indenter left at tip_y 0 starts out touching the top of the film
+    if settings.indenter != indenter::Shape::None && settings.tip_y == 0.0 {
+      settings.tip_y = grid.iter().map(|c| c.pos.y + c.radius).fold(f64::MIN, f64::max);
+    }
+
     Ok(grid)
 }
+
+// Bonds every cell to its neighbors with the global spring constant, scaled for the species
+// of the pair, and rest lengths. Grids written before bonds were stored get their bonds this way.
+pub fn link_neighbors(grid: &mut [cell::Cell], settings: &settings::Settings) {
+  let species: Vec<usize> = grid.iter().map(|c| c.species).collect();
+  for (ind, cell) in grid.iter_mut().enumerate() {
+    let k = |other: usize| settings.spring_k * species::interaction(settings, species[ind], species[other]).spring_k;
+    let close = cell.neighbor_close.iter().map(|other| {
+      cell::ForceLink::new(ind, *other, k(*other), settings.spring_relax_close, settings.bond_type)
+    });
+    let far = cell.neighbor_far.iter().map(|other| {
+      cell::ForceLink::new(ind, *other, k(*other), settings.spring_relax_far, settings.bond_type)
+    });
+    cell.links = close.chain(far).collect();
+  }
+}
+
+// Puts a bending spring between each pair of bonds that are next to each other going around
+// a cell, resting at their current angle. Pairs more than half a turn apart face out of
+// the grid and are skipped.
+fn link_angles(grid: &mut [cell::Cell], settings: &settings::Settings) {
+  for ind in 0..grid.len() {
+    let center = grid[ind].pos;
+    let mut arms: Vec<(f64, usize)> = grid[ind].links.iter().map(|link| {
+      let arm = simulation::separation(&center, &grid[link.parties.1].pos, 0.0, settings);
+      (arm.y.atan2(arm.x), link.parties.1)
+    }).collect();
+    arms.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    let mut angles = vec![];
+    for (n, (from, first)) in arms.iter().enumerate() {
+      let (to, second) = arms[(n + 1) % arms.len()];
+      if second == *first {
+        continue;
+      }
+      let mut rest = to - from;
+      if rest <= 0.0 {
+        rest += 2.0 * f64::consts::PI;
+      }
+      if rest < f64::consts::PI {
+        angles.push(cell::AngleLink::new(ind, *first, second, settings.bend_k, rest));
+      }
+    }
+    grid[ind].angles = angles;
+  }
+}
+
+// Points every cell in a random direction, from a stream apart from the ones of the bond
+// disorder and species
+fn orient(grid: &mut [cell::Cell], settings: &settings::Settings) {
+  let mut rng = noise::Rng::new(settings.seed.rotate_left(32));
+  for cell in grid.iter_mut() {
+    cell.orientation = 2.0 * f64::consts::PI * rng.uniform();
+  }
+}
+
+// Tethers anchor_fraction of the free cells, picked from a stream of their own
+fn anchor(grid: &mut [cell::Cell], settings: &settings::Settings) {
+  let mut rng = noise::Rng::new(settings.seed.rotate_left(16));
+  for cell in grid.iter_mut().filter(|c| c.grows()) {
+    if rng.uniform() > settings.anchor_fraction {
+      continue;
+    }
+    let point = match settings.anchoring {
+      cell::Anchoring::None => continue,
+      cell::Anchoring::Initial => cell.pos,
+      cell::Anchoring::Substrate => cell::Pos{x: cell.pos.x, y: settings.substrate_y},
+    };
+    cell.tether = Some(cell::Tether{point, relax_length: cell.pos.sub(&point).norm()});
+  }
+}
+
+// Scales each bond by the stiffness profile and a lognormal factor of width bond_disorder,
+// the same on both cells of the bond
+fn vary_stiffness(grid: &mut [cell::Cell], settings: &settings::Settings) {
+  let mut rng = noise::Rng::new(settings.seed);
+
+  for ind in 0..grid.len() {
+    for l in 0..grid[ind].links.len() {
+      let other = grid[ind].links[l].parties.1;
+      if other < ind {
+        continue;
+      }
+
+      let offset = simulation::separation(&grid[ind].pos, &grid[other].pos, 0.0, settings);
+      let mid_x = grid[ind].pos.x + 0.5 * offset.x;
+      let mid_y = grid[ind].pos.y + 0.5 * offset.y;
+      let mut scale = match settings.bond_profile {
+        BondProfile::Uniform => 1.0,
+        BondProfile::Core => {
+          let from_center = ((mid_x - 0.5).powi(2) + (mid_y - 0.5).powi(2)).sqrt();
+          if from_center <= settings.core_radius { settings.profile_k } else { 1.0 }
+        },
+        BondProfile::Graded => 1.0 + (settings.profile_k - 1.0) * mid_x,
+      };
+      if settings.bond_disorder > 0.0 {
+        scale *= (settings.bond_disorder * rng.gaussian()).exp();
+      }
+
+      grid[ind].links[l].value *= scale;
+      for link in grid[other].links.iter_mut().filter(|link| link.parties.1 == ind) {
+        link.value *= scale;
+      }
+    }
+  }
+}
@@ -1,11 +1,14 @@
 use plotters::prelude::*;
 
-use crate::cell;
+use crate::{cell, indenter, nutrient};
 
 // must be even
 pub const SIZE: usize = 1024;
 pub const FPS: usize = 24;
 
+// Outline of free cells of each species, going around again past the last
+const SPECIES_COLORS: [RGBColor; 4] = [BLACK, MAGENTA, CYAN, YELLOW];
+
 pub fn plot(grid: &[cell::Cell], name: &str, max_stress: f64) {
   let drawing_area = BitMapBackend::new(name, (SIZE as u32, SIZE as u32)).into_drawing_area();
   drawing_area.fill(&WHITE).unwrap();
@@ -41,7 +44,9 @@ pub fn plot(grid: &[cell::Cell], name: &str, max_stress: f64) {
   });
 }
 
-pub fn plot_buf(grid: &[cell::Cell], max_stress: f64) -> Vec<u8> {
+// The nutrient field, if given, is drawn under the cells fading from white at the supply
+// concentration to amber where there is none
+pub fn plot_buf(grid: &[cell::Cell], max_stress: f64, field: Option<&nutrient::Field>, tip: Option<&indenter::Tip>) -> Vec<u8> {
   let mut rgb: Vec<u8> = vec![];
   for _ in 0..SIZE*SIZE {
     rgb.push(0); //red
@@ -55,6 +60,21 @@ pub fn plot_buf(grid: &[cell::Cell], max_stress: f64) -> Vec<u8> {
 
     let scale = (SIZE as f64) / (1.25 - -0.25);
 
+    if let Some(field) = field {
+      let side = 1.0 / field.res as f64;
+      for (k, conc) in field.conc.iter().enumerate() {
+        let x = (k % field.res) as f64 * side;
+        let y = (k / field.res) as f64 * side;
+        let corners = (
+          (((x + 0.25) * scale) as i32, ((y + 0.25) * scale) as i32),
+          (((x + side + 0.25) * scale) as i32, ((y + side + 0.25) * scale) as i32)
+        );
+        let starved = 1.0 - conc;
+        let color = RGBColor(255, (255.0 - 80.0 * starved) as u8, (255.0 - 200.0 * starved) as u8);
+        drawing_area.draw(&Rectangle::new([corners.0, corners.1], Into::<ShapeStyle>::into(&color).filled())).unwrap();
+      }
+    }
+
     grid.iter().for_each(|cell| {
       let rad = cell.radius * scale;
       let pos = ((cell.pos.x + 0.25) * scale, (cell.pos.y + 0.25) * scale);
@@ -62,7 +82,8 @@ pub fn plot_buf(grid: &[cell::Cell], max_stress: f64) -> Vec<u8> {
       if cell.fixed {
         drawing_area.draw(&Circle::new(pos, rad as i32, Into::<ShapeStyle>::into(&GREEN).filled())).unwrap();
       } else {
-        drawing_area.draw(&Circle::new(pos, rad as i32, Into::<ShapeStyle>::into(&BLACK).filled())).unwrap();
+        let outline = SPECIES_COLORS[cell.species % SPECIES_COLORS.len()];
+        drawing_area.draw(&Circle::new(pos, rad as i32, Into::<ShapeStyle>::into(&outline).filled())).unwrap();
       }
 
       if let Some(stress) = cell.stress {
@@ -81,7 +102,21 @@ pub fn plot_buf(grid: &[cell::Cell], max_stress: f64) -> Vec<u8> {
         };
         drawing_area.draw(&Circle::new(pos, rad as i32 - 1, Into::<ShapeStyle>::into(&color).filled())).unwrap();
       }
+
+      // Tethered cells are marked in the middle, so detachment shows as the marks going out
+      if cell.tether.is_some() {
+        drawing_area.draw(&Circle::new(pos, (rad as i32 / 3).max(1), Into::<ShapeStyle>::into(&BLACK).filled())).unwrap();
+      }
     });
+
+    // The indenter goes over the cells it presses into
+    if let Some(tip) = tip {
+      let outline: Vec<(i32, i32)> = tip.outline(64).iter().map(|p| {
+        (((p.x + 0.25) * scale) as i32, ((p.y + 0.25) * scale) as i32)
+      }).collect();
+      let gray = RGBColor(128, 128, 128);
+      drawing_area.draw(&Polygon::new(outline, Into::<ShapeStyle>::into(&gray).filled())).unwrap();
+    }
   }
 
   rgb
@@ -203,6 +238,118 @@ pub fn plot_yoff(avgdist: &[(f64, f64)], name: &str) {
   )).unwrap();
 }
 
+pub fn plot_msd(msd: &[(f64, f64)], name: &str) {
+  let drawing_area = BitMapBackend::new(name, (SIZE as u32, SIZE as u32)).into_drawing_area();
+  drawing_area.fill(&WHITE).unwrap();
+
+  let max_time = msd.iter().max_by(|t1, t2| t1.0.partial_cmp(&t2.0).unwrap()).unwrap().0;
+  let max_msd = msd.iter().max_by(|t1, t2| t1.1.partial_cmp(&t2.1).unwrap()).unwrap().1;
+  let min_msd = msd.iter().min_by(|t1, t2| t1.1.partial_cmp(&t2.1).unwrap()).unwrap().1;
+
+  let mut chart = ChartBuilder::on(&drawing_area)
+    .margin(5)
+    .set_all_label_area_size(50)
+    .caption("Mean Squared Displacement vs Time", ("sans-serif", 50))
+    .build_cartesian_2d(
+      0f32..1.25 * max_time as f32,
+      if min_msd > 0.0 { 0f32 } else { min_msd as f32 * 1.25 }..1.25 * max_msd as f32
+    ).unwrap();
+
+  chart.configure_mesh().x_labels(10).y_labels(10).disable_mesh().draw().unwrap();
+
+  chart.draw_series(PointSeries::of_element(
+      msd.iter().map(|(t, avg)| (*t as f32, *avg as f32)),
+      5,
+      ShapeStyle::from(&BLACK).filled(),
+      &|coord, size, style| {
+        EmptyElement::at(coord) + Circle::new((0, 0), size, style)
+      }
+  )).unwrap();
+}
+
+// cumulative count of ruptured bonds vs time
+pub fn plot_bonds(broken: &[(f64, f64)], name: &str) {
+  let drawing_area = BitMapBackend::new(name, (SIZE as u32, SIZE as u32)).into_drawing_area();
+  drawing_area.fill(&WHITE).unwrap();
+
+  let max_time = broken.iter().max_by(|t1, t2| t1.0.partial_cmp(&t2.0).unwrap()).unwrap().0;
+  let max_broken = broken.iter().max_by(|t1, t2| t1.1.partial_cmp(&t2.1).unwrap()).unwrap().1.max(1.0);
+
+  let mut chart = ChartBuilder::on(&drawing_area)
+    .margin(5)
+    .set_all_label_area_size(50)
+    .caption("Broken Bonds vs Time", ("sans-serif", 50))
+    .build_cartesian_2d(0f32..1.25 * max_time as f32, 0f32..1.25 * max_broken as f32).unwrap();
+
+  chart.configure_mesh().x_labels(10).y_labels(10).disable_mesh().draw().unwrap();
+
+  chart.draw_series(PointSeries::of_element(
+      broken.iter().map(|(t, n)| (*t as f32, *n as f32)),
+      5,
+      ShapeStyle::from(&BLACK).filled(),
+      &|coord, size, style| {
+        EmptyElement::at(coord) + Circle::new((0, 0), size, style)
+      }
+  )).unwrap();
+}
+
+// cumulative count of detached tethers vs time
+pub fn plot_detached(detached: &[(f64, f64)], name: &str) {
+  let drawing_area = BitMapBackend::new(name, (SIZE as u32, SIZE as u32)).into_drawing_area();
+  drawing_area.fill(&WHITE).unwrap();
+
+  let max_time = detached.iter().max_by(|t1, t2| t1.0.partial_cmp(&t2.0).unwrap()).unwrap().0;
+  let max_detached = detached.iter().max_by(|t1, t2| t1.1.partial_cmp(&t2.1).unwrap()).unwrap().1.max(1.0);
+
+  let mut chart = ChartBuilder::on(&drawing_area)
+    .margin(5)
+    .set_all_label_area_size(50)
+    .caption("Detached Cells vs Time", ("sans-serif", 50))
+    .build_cartesian_2d(0f32..1.25 * max_time as f32, 0f32..1.25 * max_detached as f32).unwrap();
+
+  chart.configure_mesh().x_labels(10).y_labels(10).disable_mesh().draw().unwrap();
+
+  chart.draw_series(PointSeries::of_element(
+      detached.iter().map(|(t, n)| (*t as f32, *n as f32)),
+      5,
+      ShapeStyle::from(&BLACK).filled(),
+      &|coord, size, style| {
+        EmptyElement::at(coord) + Circle::new((0, 0), size, style)
+      }
+  )).unwrap();
+}
+
+// Force the film pushes back on the indenter with vs how deep it is
+pub fn plot_indentation(indentation: &[(f64, f64)], name: &str) {
+  let drawing_area = BitMapBackend::new(name, (SIZE as u32, SIZE as u32)).into_drawing_area();
+  drawing_area.fill(&WHITE).unwrap();
+
+  let max_depth = indentation.iter().map(|i| i.0).fold(0.0, f64::max);
+  let min_depth = indentation.iter().map(|i| i.0).fold(0.0, f64::min);
+  let max_force = indentation.iter().map(|i| i.1).fold(0.0, f64::max);
+  let min_force = indentation.iter().map(|i| i.1).fold(0.0, f64::min);
+
+  let mut chart = ChartBuilder::on(&drawing_area)
+    .margin(5)
+    .set_all_label_area_size(50)
+    .caption("Indenter Force vs Depth", ("sans-serif", 50))
+    .build_cartesian_2d(
+      min_depth as f32 * 1.25..(max_depth as f32 * 1.25).max(min_depth as f32 * -0.25 + f32::EPSILON),
+      min_force as f32 * 1.25..(max_force as f32 * 1.25).max(min_force as f32 * -0.25 + f32::EPSILON)
+    ).unwrap();
+
+  chart.configure_mesh().x_labels(10).y_labels(10).disable_mesh().draw().unwrap();
+
+  chart.draw_series(PointSeries::of_element(
+      indentation.iter().map(|(depth, force)| (*depth as f32, *force as f32)),
+      5,
+      ShapeStyle::from(&BLACK).filled(),
+      &|coord, size, style| {
+        EmptyElement::at(coord) + Circle::new((0, 0), size, style)
+      }
+  )).unwrap();
+}
+
 // avg stress vs avg strain
 pub fn plot_stressstrain(strstr: &[(f64, f64)], name: &str) {
   let drawing_area = BitMapBackend::new(name, (SIZE as u32, SIZE as u32)).into_drawing_area();
@@ -233,3 +380,95 @@ pub fn plot_stressstrain(strstr: &[(f64, f64)], name: &str) {
       }
   )).unwrap();
 }
+
+// Bulk xy stress against the applied Lees-Edwards shear strain
+pub fn plot_shear(shear: &[(f64, f64)], name: &str) {
+  let drawing_area = BitMapBackend::new(name, (SIZE as u32, SIZE as u32)).into_drawing_area();
+  drawing_area.fill(&WHITE).unwrap();
+
+  let max_strain = shear.iter().map(|s| s.0).fold(0.0, f64::max);
+  let min_strain = shear.iter().map(|s| s.0).fold(0.0, f64::min);
+  let max_stress = shear.iter().map(|s| s.1).fold(0.0, f64::max);
+  let min_stress = shear.iter().map(|s| s.1).fold(0.0, f64::min);
+
+  let mut chart = ChartBuilder::on(&drawing_area)
+    .margin(5)
+    .set_all_label_area_size(50)
+    .caption("Shear Stress vs Shear Strain", ("sans-serif", 50))
+    .build_cartesian_2d(
+      min_strain as f32 * 1.25..(max_strain as f32 * 1.25).max(min_strain as f32 * -0.25 + f32::EPSILON),
+      min_stress as f32 * 1.25..(max_stress as f32 * 1.25).max(min_stress as f32 * -0.25 + f32::EPSILON)
+    ).unwrap();
+
+  chart.configure_mesh().x_labels(10).y_labels(10).disable_mesh().draw().unwrap();
+
+  chart.draw_series(PointSeries::of_element(
+      shear.iter().map(|(strain, stress)| (*strain as f32, *stress as f32)),
+      5,
+      ShapeStyle::from(&BLACK).filled(),
+      &|coord, size, style| {
+        EmptyElement::at(coord) + Circle::new((0, 0), size, style)
+      }
+  )).unwrap();
+}
+
+// Reaction force of displacement driven cells against their imposed displacement
+pub fn plot_reaction(reaction: &[(f64, f64)], name: &str) {
+  let drawing_area = BitMapBackend::new(name, (SIZE as u32, SIZE as u32)).into_drawing_area();
+  drawing_area.fill(&WHITE).unwrap();
+
+  let max_disp = reaction.iter().map(|r| r.0).fold(0.0, f64::max);
+  let min_disp = reaction.iter().map(|r| r.0).fold(0.0, f64::min);
+  let max_force = reaction.iter().map(|r| r.1).fold(0.0, f64::max);
+  let min_force = reaction.iter().map(|r| r.1).fold(0.0, f64::min);
+
+  let mut chart = ChartBuilder::on(&drawing_area)
+    .margin(5)
+    .set_all_label_area_size(50)
+    .caption("Reaction Force vs Displacement", ("sans-serif", 50))
+    .build_cartesian_2d(
+      min_disp as f32 * 1.25..(max_disp as f32 * 1.25).max(min_disp as f32 * -0.25 + f32::EPSILON),
+      min_force as f32 * 1.25..(max_force as f32 * 1.25).max(min_force as f32 * -0.25 + f32::EPSILON)
+    ).unwrap();
+
+  chart.configure_mesh().x_labels(10).y_labels(10).disable_mesh().draw().unwrap();
+
+  chart.draw_series(PointSeries::of_element(
+      reaction.iter().map(|(disp, force)| (*disp as f32, *force as f32)),
+      5,
+      ShapeStyle::from(&BLACK).filled(),
+      &|coord, size, style| {
+        EmptyElement::at(coord) + Circle::new((0, 0), size, style)
+      }
+  )).unwrap();
+}
+
+// Virial stress of the self-propulsion vs time
+pub fn plot_active(active: &[(f64, f64)], name: &str) {
+  let drawing_area = BitMapBackend::new(name, (SIZE as u32, SIZE as u32)).into_drawing_area();
+  drawing_area.fill(&WHITE).unwrap();
+
+  let max_time = active.iter().map(|a| a.0).fold(0.0, f64::max);
+  let max_active = active.iter().map(|a| a.1).fold(0.0, f64::max);
+  let min_active = active.iter().map(|a| a.1).fold(0.0, f64::min);
+
+  let mut chart = ChartBuilder::on(&drawing_area)
+    .margin(5)
+    .set_all_label_area_size(50)
+    .caption("Active Stress vs Time", ("sans-serif", 50))
+    .build_cartesian_2d(
+      0f32..(1.25 * max_time as f32).max(f32::EPSILON),
+      min_active as f32 * 1.25..(max_active as f32 * 1.25).max(min_active as f32 * -0.25 + f32::EPSILON)
+    ).unwrap();
+
+  chart.configure_mesh().x_labels(10).y_labels(10).disable_mesh().draw().unwrap();
+
+  chart.draw_series(PointSeries::of_element(
+      active.iter().map(|(t, stress)| (*t as f32, *stress as f32)),
+      5,
+      ShapeStyle::from(&BLACK).filled(),
+      &|coord, size, style| {
+        EmptyElement::at(coord) + Circle::new((0, 0), size, style)
+      }
+  )).unwrap();
+}
@@ -0,0 +1,141 @@
+use serde::{Serialize, Deserialize};
+use crate::{cell, forces, settings};
+
+// Shape of the rigid probe pressed into the film from above
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Shape {
+  None,
+  Circle, // a disk of tip_radius
+  Cap, // the bottom cap_height of the disk, flat on top
+}
+
+pub fn shape_enum(name: &str) -> Option<Shape> {
+  match name {
+    "none" => Some(Shape::None),
+    "circle" => Some(Shape::Circle),
+    "cap" => Some(Shape::Cap),
+    _ => None
+  }
+}
+
+// How deep the probe is pressed over time
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Path {
+  Ramp, // down at indent_rate until indent_depth, then held
+  Triangle, // down at indent_rate to indent_depth and back up, over and over
+  Table, // depths interpolated from indent_table
+}
+
+pub fn path_enum(name: &str) -> Option<Path> {
+  match name {
+    "ramp" => Some(Path::Ramp),
+    "triangle" => Some(Path::Triangle),
+    "table" => Some(Path::Table),
+    _ => None
+  }
+}
+
+// Where the probe is at some time. The lowest point of the tip is depth below tip_y.
+pub struct Tip {
+  pub center: cell::Pos, // of the disk the tip is cut from
+  pub radius: f64,
+  pub cap_height: f64,
+  pub depth: f64
+}
+
+// Depth of the probe below tip_y at time t
+pub fn depth(t: f64, s: &settings::Settings) -> f64 {
+  match s.indent_path {
+    Path::Ramp => (s.indent_rate * t).min(s.indent_depth),
+    Path::Triangle => {
+      let half = s.indent_depth / s.indent_rate;
+      let phase = t.rem_euclid(2.0 * half);
+      s.indent_rate * if phase < half { phase } else { 2.0 * half - phase }
+    },
+    Path::Table => forces::interpolate(&s.indent_table, t),
+  }
+}
+
+pub fn tip(t: f64, s: &settings::Settings) -> Option<Tip> {
+  let cap_height = match s.indenter {
+    Shape::None => return None,
+    Shape::Circle => 2.0 * s.tip_radius,
+    Shape::Cap => s.cap_height,
+  };
+  let depth = depth(t, s);
+  Some(Tip {
+    center: cell::Pos{x: s.tip_x, y: s.tip_y - depth + s.tip_radius},
+    radius: s.tip_radius,
+    cap_height,
+    depth
+  })
+}
+
+impl Tip {
+  // Height of the flat top over the center of the disk, and its half width
+  fn chord(&self) -> (f64, f64) {
+    let height = self.cap_height - self.radius;
+    (height, (self.radius * self.radius - height * height).max(0.0).sqrt())
+  }
+
+  // Distance from pos to the surface of the tip, negative inside, and the direction out of
+  // the tip there
+  fn contact(&self, pos: &cell::Pos) -> Option<(f64, cell::Pos)> {
+    let q = pos.sub(&self.center);
+    let (height, half_width) = self.chord();
+    let from_center = q.norm();
+
+    // Nearest points on the flat top and on the round part, if the round part faces pos
+    let on_top = cell::Pos{x: q.x.clamp(-half_width, half_width), y: height};
+    let mut nearest = (q.sub(&on_top).norm(), on_top);
+    if from_center > 0.0 && self.radius * q.y / from_center <= height {
+      let on_arc = cell::Pos{x: self.radius * q.x / from_center, y: self.radius * q.y / from_center};
+      let dist = (from_center - self.radius).abs();
+      if dist < nearest.0 {
+        nearest = (dist, on_arc);
+      }
+    }
+
+    let (dist, point) = nearest;
+    if dist == 0.0 {
+      return None;
+    }
+    let inside = from_center < self.radius && q.y < height;
+    let sign = if inside { -1.0 } else { 1.0 };
+    let offset = q.sub(&point);
+    Some((sign * dist, cell::Pos{x: sign * offset.x / dist, y: sign * offset.y / dist}))
+  }
+
+  // Contact force of the tip on a cell, indent_k times how far the cell overlaps it
+  pub fn force(&self, c: &cell::Cell, s: &settings::Settings) -> cell::Pos {
+    match self.contact(&c.pos) {
+      Some((dist, normal)) if dist < c.radius => {
+        let push = s.indent_k * (c.radius - dist);
+        cell::Pos{x: push * normal.x, y: push * normal.y}
+      },
+      _ => cell::Pos{x: 0.0, y: 0.0}
+    }
+  }
+
+  // Outline of the tip for drawing, going around from the left end of the flat top
+  pub fn outline(&self, points: usize) -> Vec<cell::Pos> {
+    let (height, half_width) = self.chord();
+    let start = height.atan2(-half_width);
+    let mut end = height.atan2(half_width);
+    if end <= start {
+      end += 2.0 * std::f64::consts::PI;
+    }
+    (0..=points).map(|n| {
+      let angle = start + (end - start) * n as f64 / points as f64;
+      cell::Pos{x: self.center.x + self.radius * angle.cos(), y: self.center.y + self.radius * angle.sin()}
+    }).collect()
+  }
+}
+
+// Force the film pushes the tip up with at time t
+pub fn reaction(grid: &[cell::Cell], t: f64, s: &settings::Settings) -> f64 {
+  match tip(t, s) {
+    Some(tip) => grid.iter().fold(0.0, |acc, c| acc - tip.force(c, s).y),
+    None => 0.0
+  }
+}
@@ -0,0 +1,472 @@
+use serde::{Serialize, Deserialize};
+use crate::{cell, settings, simulation, noise, gfx, nutrient};
+use super::RustfilmError;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write, BufRead, BufReader, BufWriter};
+use std::fmt;
+use std::error::Error;
+
+pub type Derivs = fn(f64, &mut [cell::Cell], &settings::Settings) -> Result<Vec<f64>, IntegratorError>;
+
+pub trait Integrator {
+  // Runs from the given checkpoint until settings.del_t, every step before a failure is observed
+  fn integrate(
+    &self,
+    start: Checkpoint,
+    dy: Derivs,
+    settings: &settings::Settings,
+    observer: &mut dyn Observer
+  ) -> Result<(), IntegratorError>;
+}
+
+// Why an integrator stopped before settings.del_t
+#[derive(Debug, Clone, Copy)]
+pub enum IntegratorError {
+  StepUnderflow { time: f64, dt: f64 },
+  NonFinite { time: f64, cell: usize },
+  OutOfBounds { time: f64, cell: usize, pos: cell::Pos },
+  NoConvergence { time: f64 },
+  Io { time: f64, kind: io::ErrorKind }, // an observer failed to write out the step at time
+}
+
+impl Error for IntegratorError {}
+
+impl fmt::Display for IntegratorError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      IntegratorError::StepUnderflow { time, dt } =>
+        write!(f, "step size {} fell below the minimum at t = {}", dt, time),
+      IntegratorError::NonFinite { time, cell } =>
+        write!(f, "cell {} has a non-finite position or force at t = {}", cell, time),
+      IntegratorError::OutOfBounds { time, cell, pos } =>
+        write!(f, "cell {} left the simulation bounds at ({}, {}) at t = {}", cell, pos.x, pos.y, time),
+      IntegratorError::NoConvergence { time } =>
+        write!(f, "Newton iteration failed to converge at t = {}, try a smaller dt", time),
+      IntegratorError::Io { time, kind } =>
+        write!(f, "writing the output failed at t = {}: {}", time, kind),
+    }
+  }
+}
+
+impl IntegratorError {
+  pub fn io(time: f64, error: io::Error) -> IntegratorError {
+    IntegratorError::Io{time, kind: error.kind()}
+  }
+}
+
+impl From<IntegratorError> for RustfilmError {
+  fn from(error: IntegratorError) -> RustfilmError {
+    RustfilmError{error: error.to_string()}
+  }
+}
+
+// Everything an integrator needs to continue a run exactly where it stopped.
+// Checkpoints are only taken after a step, so iter 0 means a fresh start.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Checkpoint {
+  pub iter: i32,
+  pub time: f64,
+  pub dt: Option<f64>,
+  pub state: Vec<cell::Cell>,
+  pub history: Vec<(f64, Vec<cell::Cell>)>, // (time, state) kept by multistep methods
+  pub nflag: bool, // history holds unaccepted Runge-Kutta starting values
+  pub last: bool, // next accepted step reaches settings.del_t
+  #[serde(default)]
+  pub last_deriv: Vec<f64>, // derivative at state, reused by the next step
+  #[serde(default)]
+  pub rng: Option<noise::Rng>, // generator of stochastic integrators
+  #[serde(default)]
+  pub field: Option<nutrient::Field>, // nutrient field at state
+}
+
+impl Checkpoint {
+  pub fn new(grid: &[cell::Cell]) -> Checkpoint {
+    Checkpoint::at(0, 0.0, grid)
+  }
+
+  pub fn at(iter: i32, time: f64, state: &[cell::Cell]) -> Checkpoint {
+    Checkpoint {
+      iter,
+      time,
+      dt: None,
+      state: state.to_vec(),
+      history: vec![],
+      nflag: false,
+      last: false,
+      last_deriv: vec![],
+      rng: None,
+      field: None
+    }
+  }
+}
+
+// Receives every accepted step of an integrator, in order
+pub trait Observer {
+  // Fails if what the observer works out from the state can't be
+  fn observe(&mut self, iter: i32, time: f64, state: &[cell::Cell], field: Option<&nutrient::Field>) -> Result<(), IntegratorError>;
+
+  // Asked after each observed step whether a checkpoint should be handed over
+  fn wants_checkpoint(&mut self, _time: f64) -> bool {
+    false
+  }
+
+  // Fails if the checkpoint or what comes before it can't be written out
+  fn checkpoint(&mut self, _checkpoint: &Checkpoint) -> Result<(), IntegratorError> {
+    Ok(())
+  }
+}
+
+// Keeps the whole trajectory in memory
+impl Observer for Vec<(i32, f64, Vec<cell::Cell>)> {
+  fn observe(&mut self, iter: i32, time: f64, state: &[cell::Cell], _field: Option<&nutrient::Field>) -> Result<(), IntegratorError> {
+    self.push((iter, time, state.to_vec()));
+    Ok(())
+  }
+}
+
+// Writes one RON (iter, time, state) tuple per line
+pub struct TrajectoryWriter {
+  file: BufWriter<File>
+}
+
+impl TrajectoryWriter {
+  pub fn new(name: &str) -> Result<TrajectoryWriter, RustfilmError> {
+    match File::create(name) {
+      Ok(file) => Ok(TrajectoryWriter { file: BufWriter::new(file) }),
+      Err(_e) => Err(RustfilmError{error: "Failed to create trajectory file".to_string()})
+    }
+  }
+
+  // Continue an existing trajectory, dropping any steps written after iteration iter
+  pub fn resume(name: &str, iter: i32) -> Result<TrajectoryWriter, RustfilmError> {
+    let kept_name = format!("{}.tmp", name);
+    {
+      let file = match File::open(name) {
+        Ok(file) => file,
+        Err(_e) => return Err(RustfilmError{error: "Failed to open trajectory file".to_string()})
+      };
+      let mut kept = match File::create(&kept_name) {
+        Ok(file) => BufWriter::new(file),
+        Err(_e) => return Err(RustfilmError{error: "Failed to create trajectory file".to_string()})
+      };
+
+      for line in BufReader::new(file).lines() {
+        let line = match line {
+          Ok(line) => line,
+          Err(_e) => return Err(RustfilmError{error: "Failed to read trajectory file".to_string()})
+        };
+        // Lines start with "(iter,"
+        let step = line.trim_start_matches('(').split(',').next().unwrap_or("").parse::<i32>();
+        match step {
+          Ok(step) if step <= iter => {},
+          _ => break
+        }
+        if writeln!(kept, "{}", line).is_err() {
+          return Err(RustfilmError{error: "Failed to write trajectory file".to_string()});
+        }
+      }
+    }
+
+    if std::fs::rename(&kept_name, name).is_err() {
+      return Err(RustfilmError{error: "Failed to replace trajectory file".to_string()});
+    }
+    match OpenOptions::new().append(true).open(name) {
+      Ok(file) => Ok(TrajectoryWriter { file: BufWriter::new(file) }),
+      Err(_e) => Err(RustfilmError{error: "Failed to open trajectory file".to_string()})
+    }
+  }
+
+  pub fn flush(&mut self) -> io::Result<()> {
+    self.file.flush()
+  }
+}
+
+impl Observer for TrajectoryWriter {
+  fn observe(&mut self, iter: i32, time: f64, state: &[cell::Cell], _field: Option<&nutrient::Field>) -> Result<(), IntegratorError> {
+    let ron = match ron::to_string(&(iter, time, state)) {
+      Ok(ron) => ron,
+      Err(_e) => return Err(IntegratorError::Io{time, kind: io::ErrorKind::InvalidData})
+    };
+    writeln!(self.file, "{}", ron).map_err(|e| IntegratorError::io(time, e))
+  }
+
+  // Everything up to a checkpoint has to be on disk before the checkpoint is
+  fn checkpoint(&mut self, checkpoint: &Checkpoint) -> Result<(), IntegratorError> {
+    self.flush().map_err(|e| IntegratorError::io(checkpoint.time, e))
+  }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Copy, Clone)]
+pub enum IntegratorFunc {
+  Euler,
+  Rk,
+  PredictorCorrector,
+  RkAdaptive,
+  Rk45,
+  PredictorCorrectorAdaptive,
+  Verlet,
+  EulerMaruyama,
+  BackwardEuler,
+  Bdf2,
+  DormandPrince,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+pub struct IntegratorParams {
+  pub dt: f64,
+  pub tol: f64,
+  pub dt_min: f64,
+  pub dt_max: f64,
+  #[serde(default)]
+  pub sample_dt: Option<f64>,
+}
+
+pub struct Euler {
+  pub dt: f64
+}
+
+pub struct Rk {
+  pub dt: f64
+}
+
+pub struct PredictorCorrector {
+  pub dt: f64
+}
+
+pub struct RkAdaptive {
+  pub tol: f64
+}
+
+pub struct Rk45 {
+  pub epsilon: f64,
+  pub dt_min: f64,
+  pub dt_max: f64
+}
+
+pub struct PredictorCorrectorAdaptive {
+  pub epsilon: f64,
+  pub dt_min: f64,
+  pub dt_max: f64
+}
+
+// Overdamped Langevin dynamics at settings.temperature
+pub struct EulerMaruyama {
+  pub dt: f64
+}
+
+// Observes the dense output at multiples of sample_dt rather than at step times
+pub struct DormandPrince {
+  pub epsilon: f64,
+  pub dt_min: f64,
+  pub dt_max: f64,
+  pub sample_dt: f64
+}
+
+// Implicit integrators for stiff grids
+pub struct BackwardEuler {
+  pub dt: f64
+}
+
+pub struct Bdf2 {
+  pub dt: f64
+}
+
+// Second order integrator for inertial grids, steps with net forces rather than derivs
+pub struct Verlet {
+  pub dt: f64,
+  pub force: Derivs
+}
+
+impl Integrator for Euler {
+  fn integrate(&self, start: Checkpoint, dy: Derivs, settings: &settings::Settings, observer: &mut dyn Observer) -> Result<(), IntegratorError> {
+    simulation::euler(start, self.dt, dy, settings, observer)
+  }
+}
+
+impl Integrator for Rk {
+  fn integrate(&self, start: Checkpoint, dy: Derivs, settings: &settings::Settings, observer: &mut dyn Observer) -> Result<(), IntegratorError> {
+    simulation::rk(start, self.dt, dy, settings, observer)
+  }
+}
+
+impl Integrator for PredictorCorrector {
+  fn integrate(&self, start: Checkpoint, dy: Derivs, settings: &settings::Settings, observer: &mut dyn Observer) -> Result<(), IntegratorError> {
+    simulation::predictor_corrector(start, self.dt, dy, settings, observer)
+  }
+}
+
+impl Integrator for RkAdaptive {
+  fn integrate(&self, start: Checkpoint, dy: Derivs, settings: &settings::Settings, observer: &mut dyn Observer) -> Result<(), IntegratorError> {
+    simulation::rk_adaptive(start, self.tol, dy, settings, observer)
+  }
+}
+
+impl Integrator for Rk45 {
+  fn integrate(&self, start: Checkpoint, dy: Derivs, settings: &settings::Settings, observer: &mut dyn Observer) -> Result<(), IntegratorError> {
+    simulation::rk45(start, self.epsilon, self.dt_min, self.dt_max, dy, settings, observer)
+  }
+}
+
+impl Integrator for PredictorCorrectorAdaptive {
+  fn integrate(&self, start: Checkpoint, dy: Derivs, settings: &settings::Settings, observer: &mut dyn Observer) -> Result<(), IntegratorError> {
+    simulation::predictor_corrector_adaptive(start, self.epsilon, self.dt_min, self.dt_max, dy, settings, observer)
+  }
+}
+
+impl Integrator for Verlet {
+  fn integrate(&self, start: Checkpoint, _dy: Derivs, settings: &settings::Settings, observer: &mut dyn Observer) -> Result<(), IntegratorError> {
+    simulation::verlet(start, self.dt, self.force, settings, observer)
+  }
+}
+
+impl Integrator for EulerMaruyama {
+  fn integrate(&self, start: Checkpoint, dy: Derivs, settings: &settings::Settings, observer: &mut dyn Observer) -> Result<(), IntegratorError> {
+    simulation::euler_maruyama(start, self.dt, dy, settings, observer)
+  }
+}
+
+impl Integrator for BackwardEuler {
+  fn integrate(&self, start: Checkpoint, dy: Derivs, settings: &settings::Settings, observer: &mut dyn Observer) -> Result<(), IntegratorError> {
+    simulation::backward_euler(start, self.dt, dy, settings, observer)
+  }
+}
+
+impl Integrator for Bdf2 {
+  fn integrate(&self, start: Checkpoint, dy: Derivs, settings: &settings::Settings, observer: &mut dyn Observer) -> Result<(), IntegratorError> {
+    simulation::bdf2(start, self.dt, dy, settings, observer)
+  }
+}
+
+impl Integrator for DormandPrince {
+  fn integrate(&self, start: Checkpoint, dy: Derivs, settings: &settings::Settings, observer: &mut dyn Observer) -> Result<(), IntegratorError> {
+    simulation::dormand_prince(start, self.epsilon, self.dt_min, self.dt_max, self.sample_dt, dy, settings, observer)
+  }
+}
+
+impl IntegratorFunc {
+  // Whether the integrator needs an inertial grid (cells with velocities and masses)
+  pub fn inertial(&self) -> bool {
+    *self == IntegratorFunc::Verlet
+  }
+
+  // Whether the integrator adds thermal noise from settings.temperature
+  pub fn stochastic(&self) -> bool {
+    *self == IntegratorFunc::EulerMaruyama
+  }
+}
+
+pub fn func_enum(name: &str) -> Option<IntegratorFunc> {
+  match name {
+    "euler" => Some(IntegratorFunc::Euler),
+    "rk" => Some(IntegratorFunc::Rk),
+    "predictor_corrector" => Some(IntegratorFunc::PredictorCorrector),
+    "rk_adaptive" => Some(IntegratorFunc::RkAdaptive),
+    "rk45" => Some(IntegratorFunc::Rk45),
+    "predictor_corrector_adaptive" => Some(IntegratorFunc::PredictorCorrectorAdaptive),
+    "verlet" => Some(IntegratorFunc::Verlet),
+    "euler_maruyama" => Some(IntegratorFunc::EulerMaruyama),
+    "backward_euler" => Some(IntegratorFunc::BackwardEuler),
+    "bdf2" => Some(IntegratorFunc::Bdf2),
+    "dormand_prince" => Some(IntegratorFunc::DormandPrince),
+    _ => None
+  }
+}
+
+pub fn integrator(e: &IntegratorFunc, p: &IntegratorParams) -> Box<dyn Integrator> {
+  match e {
+    IntegratorFunc::Euler => Box::new(Euler { dt: p.dt }),
+    IntegratorFunc::Rk => Box::new(Rk { dt: p.dt }),
+    IntegratorFunc::PredictorCorrector => Box::new(PredictorCorrector { dt: p.dt }),
+    IntegratorFunc::RkAdaptive => Box::new(RkAdaptive { tol: p.tol }),
+    IntegratorFunc::Rk45 => Box::new(Rk45 { epsilon: p.tol, dt_min: p.dt_min, dt_max: p.dt_max }),
+    IntegratorFunc::PredictorCorrectorAdaptive => Box::new(PredictorCorrectorAdaptive {
+      epsilon: p.tol,
+      dt_min: p.dt_min,
+      dt_max: p.dt_max
+    }),
+    IntegratorFunc::Verlet => Box::new(Verlet { dt: p.dt, force: simulation::net_force }),
+    IntegratorFunc::EulerMaruyama => Box::new(EulerMaruyama { dt: p.dt }),
+    IntegratorFunc::BackwardEuler => Box::new(BackwardEuler { dt: p.dt }),
+    IntegratorFunc::Bdf2 => Box::new(Bdf2 { dt: p.dt }),
+    IntegratorFunc::DormandPrince => Box::new(DormandPrince {
+      epsilon: p.tol,
+      dt_min: p.dt_min,
+      dt_max: p.dt_max,
+      sample_dt: p.sample_dt.unwrap_or(1.0 / gfx::FPS as f64)
+    }),
+  }
+}
+
+impl IntegratorParams {
+  pub fn new() -> IntegratorParams {
+    IntegratorParams {
+      dt: 0.01,
+      tol: 0.01,
+      dt_min: 0.001,
+      dt_max: 0.1,
+      sample_dt: None,
+    }
+  }
+
+  pub fn args(&mut self, matches: &clap::ArgMatches) -> Option<RustfilmError> {
+    if let Some(dt) = matches.value_of("dt") {
+      match dt.parse::<f64>() {
+        Ok(dt) => self.dt = dt,
+        Err(_e) => return Some(RustfilmError{error: "dt failed to parse".to_string()})
+      }
+      if self.dt <= 0.0 {
+        return Some(RustfilmError{error: "dt must be positive".to_string()});
+      }
+    }
+
+    if let Some(tol) = matches.value_of("tol") {
+      match tol.parse::<f64>() {
+        Ok(tol) => self.tol = tol,
+        Err(_e) => return Some(RustfilmError{error: "tol failed to parse".to_string()})
+      }
+      if self.tol <= 0.0 {
+        return Some(RustfilmError{error: "tol must be positive".to_string()});
+      }
+    }
+
+    if let Some(dt_min) = matches.value_of("dt_min") {
+      match dt_min.parse::<f64>() {
+        Ok(dt_min) => self.dt_min = dt_min,
+        Err(_e) => return Some(RustfilmError{error: "dt_min failed to parse".to_string()})
+      }
+      if self.dt_min <= 0.0 {
+        return Some(RustfilmError{error: "dt_min must be positive".to_string()});
+      }
+    }
+
+    if let Some(dt_max) = matches.value_of("dt_max") {
+      match dt_max.parse::<f64>() {
+        Ok(dt_max) => self.dt_max = dt_max,
+        Err(_e) => return Some(RustfilmError{error: "dt_max failed to parse".to_string()})
+      }
+      if self.dt_max <= 0.0 {
+        return Some(RustfilmError{error: "dt_max must be positive".to_string()});
+      }
+    }
+
+    if let Some(sample_dt) = matches.value_of("sample_dt") {
+      match sample_dt.parse::<f64>() {
+        Ok(sample_dt) if sample_dt > 0.0 => self.sample_dt = Some(sample_dt),
+        Ok(_) => return Some(RustfilmError{error: "sample_dt must be positive".to_string()}),
+        Err(_e) => return Some(RustfilmError{error: "sample_dt failed to parse".to_string()})
+      }
+    }
+
+    if self.dt_min > self.dt_max {
+      return Some(RustfilmError{error: "dt_min must not exceed dt_max".to_string()});
+    }
+
+    None
+  }
+}
+
+impl Default for IntegratorParams {
+  fn default() -> IntegratorParams {
+    IntegratorParams::new()
+  }
+}
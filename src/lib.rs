@@ -5,7 +5,13 @@ pub mod settings;
 pub mod generation;
 pub mod gfx;
 pub mod simulation;
+pub mod integrator;
+pub mod noise;
 pub mod quadtree;
+pub mod nutrient;
+pub mod species;
+pub mod flow;
+pub mod indenter;
 
 use std::fmt;
 use std::error::Error;
@@ -2,15 +2,14 @@ extern crate clap;
 extern crate rustfilm;
 extern crate ron;
 extern crate num;
-extern crate rayon;
 extern crate num_cpus;
 extern crate x264;
 
 use clap::{Arg, App, SubCommand};
-use rustfilm::{update, generation, settings, gfx, simulation, cell};
-use std::fs::File;
-use std::io::{Write, BufRead, BufReader};
-use rayon::prelude::*;
+use serde::{Serialize, Deserialize};
+use rustfilm::{update, generation, settings, gfx, simulation, integrator, cell, forces, nutrient, indenter};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write, BufRead, BufReader, BufWriter};
 
 fn main() {
   let matches = App::new("rustfilm").version("1.0")
@@ -34,7 +33,7 @@ fn main() {
                     .arg(Arg::with_name("fixed")
                       .long("fixed")
                       .value_name("FUNC")
-                      .help("Choose what fixing funcion to use")
+                      .help("Choose what fixing funcion to use (constrained, sine, pluck, step, ramp, square, triangle, chirp, multisine, table, disp_ramp, disp_sine, disp_table, shear, compress, biaxial, equibiaxial)")
                       .takes_value(true)
                     )
                     .arg(Arg::with_name("nrows")
@@ -55,6 +54,368 @@ fn main() {
                       .help("Spring constant")
                       .takes_value(true)
                     )
+                    .arg(Arg::with_name("bond_profile")
+                      .long("bond_profile")
+                      .value_name("PROFILE")
+                      .help("How spring constants vary over the grid (uniform, core, graded)")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("profile_k")
+                      .long("profile_k")
+                      .value_name("FLOAT")
+                      .help("Stiffness of the core, or of the right edge when graded, relative to spring_k")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("core_radius")
+                      .long("core_radius")
+                      .value_name("FLOAT")
+                      .help("Radius of the stiff core, and of species 1 in the core species pattern")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("bond_disorder")
+                      .long("bond_disorder")
+                      .value_name("FLOAT")
+                      .help("Width of the lognormal distribution spring constants are drawn from")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("rupture_strain")
+                      .long("rupture_strain")
+                      .value_name("FLOAT")
+                      .help("Bonds break when stretched by this fraction of their rest length (0 for never)")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("rupture_force")
+                      .long("rupture_force")
+                      .value_name("FLOAT")
+                      .help("Bonds break when their tension exceeds this force (0 for never)")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("capture_dist")
+                      .long("capture_dist")
+                      .value_name("FLOAT")
+                      .help("Unbonded cells closer than this form a new bond (0 for never)")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("bond_type")
+                      .long("bond_type")
+                      .value_name("TYPE")
+                      .help("Rheology of bonds (elastic, kelvin_voigt, maxwell)")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("kv_viscosity")
+                      .long("kv_viscosity")
+                      .value_name("FLOAT")
+                      .help("Viscosity of the dashpot alongside kelvin_voigt bonds")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("maxwell_viscosity")
+                      .long("maxwell_viscosity")
+                      .value_name("FLOAT")
+                      .help("Viscosity of the dashpot in series with maxwell bonds")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("bend_k")
+                      .long("bend_k")
+                      .value_name("FLOAT")
+                      .help("Stiffness of bending springs between neighboring bonds (0 for none)")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("wall")
+                      .long("wall")
+                      .value_name("TYPE")
+                      .help("Walls confining the cells (none, channel, box, circle, polygon)")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("wall_potential")
+                      .long("wall_potential")
+                      .value_name("TYPE")
+                      .help("How walls push on cells (harmonic, lj, wca)")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("wall_xmin")
+                      .long("wall_xmin")
+                      .value_name("FLOAT")
+                      .help("Left wall of the box")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("wall_xmax")
+                      .long("wall_xmax")
+                      .value_name("FLOAT")
+                      .help("Right wall of the box")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("wall_ymin")
+                      .long("wall_ymin")
+                      .value_name("FLOAT")
+                      .help("Bottom wall of the channel or box")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("wall_ymax")
+                      .long("wall_ymax")
+                      .value_name("FLOAT")
+                      .help("Top wall of the channel or box")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("well_x")
+                      .long("well_x")
+                      .value_name("FLOAT")
+                      .help("x of the center of the circular well")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("well_y")
+                      .long("well_y")
+                      .value_name("FLOAT")
+                      .help("y of the center of the circular well")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("well_radius")
+                      .long("well_radius")
+                      .value_name("FLOAT")
+                      .help("Radius of the circular well")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("wall_polygon")
+                      .long("wall_polygon")
+                      .value_name("FILE")
+                      .help("File of x y rows giving the vertices of the polygon wall in order")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("periodic_x")
+                      .long("periodic_x")
+                      .help("Wrap the grid around in x, bonding the last column to the first")
+                    )
+                    .arg(Arg::with_name("periodic_y")
+                      .long("periodic_y")
+                      .help("Wrap the grid around in y, bonding the last row to the first")
+                    )
+                    .arg(Arg::with_name("lees_edwards")
+                      .long("lees_edwards")
+                      .value_name("NAME")
+                      .help("Shear the periodic images in y: none, steady or oscillatory. Makes the grid periodic")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("shear_rate")
+                      .long("shear_rate")
+                      .value_name("FLOAT")
+                      .help("Shear rate of steady Lees-Edwards shear")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("shear_strain")
+                      .long("shear_strain")
+                      .value_name("FLOAT")
+                      .help("Strain amplitude of oscillatory Lees-Edwards shear, at sineomega")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("growth")
+                      .long("growth")
+                      .value_name("NAME")
+                      .help("How free cells grow and divide: none, exponential or monod")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("growth_rate")
+                      .long("growth_rate")
+                      .value_name("FLOAT")
+                      .help("Rate the area of growing cells grows at")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("division_radius")
+                      .long("division_radius")
+                      .value_name("FLOAT")
+                      .help("Radius cells divide at, twice their starting area by default")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("nutrient")
+                      .long("nutrient")
+                      .value_name("FLOAT")
+                      .help("Nutrient for monod growth, in starting areas of the colony it can grow")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("monod_k")
+                      .long("monod_k")
+                      .value_name("FLOAT")
+                      .help("Nutrient left, or concentration in the nutrient field, at which monod growth is at half of growth_rate")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("nutrient_supply")
+                      .long("nutrient_supply")
+                      .value_name("NAME")
+                      .help("Edges a nutrient field is fed from: none, edges, bottom or top")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("nutrient_res")
+                      .long("nutrient_res")
+                      .value_name("INT")
+                      .help("Squares per side of the nutrient field")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("nutrient_diffusion")
+                      .long("nutrient_diffusion")
+                      .value_name("FLOAT")
+                      .help("Diffusion constant of the nutrient field")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("nutrient_uptake")
+                      .long("nutrient_uptake")
+                      .value_name("FLOAT")
+                      .help("Nutrient taken up per area of cell and concentration")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("nutrient_stiffness")
+                      .long("nutrient_stiffness")
+                      .value_name("FLOAT")
+                      .help("Fraction of bond stiffness lost by cells without nutrient")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("species")
+                      .long("species")
+                      .value_name("FILE")
+                      .help("File of \"species RADIUS GROWTH\" lines and \"pair FIRST SECOND SPRING_K REPL_EPSILON ADHESION\" lines, all relative to the global settings")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("species_pattern")
+                      .long("species_pattern")
+                      .value_name("NAME")
+                      .help("How species are laid out over the grid: uniform, random, core or stripes")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("species_fraction")
+                      .long("species_fraction")
+                      .value_name("FLOAT")
+                      .help("Fraction of cells that aren't species 0 in the random pattern")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("stripe_width")
+                      .long("stripe_width")
+                      .value_name("FLOAT")
+                      .help("Width of the stripes of the stripes pattern")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("active_force")
+                      .long("active_force")
+                      .value_name("FLOAT")
+                      .help("Self-propulsion force of free cells along their orientations (0 for none)")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("rot_diffusion")
+                      .long("rot_diffusion")
+                      .value_name("FLOAT")
+                      .help("Rotational diffusion constant of the orientations of active cells")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("anchoring")
+                      .long("anchoring")
+                      .value_name("NAME")
+                      .help("What free cells are tethered to: none, initial (where they start) or substrate (the line y = substrate_y)")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("anchor_k")
+                      .long("anchor_k")
+                      .value_name("FLOAT")
+                      .help("Spring constant of the tethers")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("anchor_fraction")
+                      .long("anchor_fraction")
+                      .value_name("FLOAT")
+                      .help("Fraction of free cells that are tethered, picked at random")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("substrate_y")
+                      .long("substrate_y")
+                      .value_name("FLOAT")
+                      .help("Height of the substrate line for substrate anchoring")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("detach_force")
+                      .long("detach_force")
+                      .value_name("FLOAT")
+                      .help("Tethers pulling harder than this detach (0 for never)")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("flow")
+                      .long("flow")
+                      .value_name("NAME")
+                      .help("Background flow dragging on free cells: none, uniform, poiseuille (between wall_ymin and wall_ymax) or table")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("flow_speed")
+                      .long("flow_speed")
+                      .value_name("FLOAT")
+                      .help("Speed along x of uniform flow, and at the middle of the channel for poiseuille flow")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("flow_drag")
+                      .long("flow_drag")
+                      .value_name("FLOAT")
+                      .help("Stokes drag coefficient of the flow per cell radius")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("flow_table")
+                      .long("flow_table")
+                      .value_name("FILE")
+                      .help("File of x y vx vy rows over a lattice of points giving the table flow")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("indenter")
+                      .long("indenter")
+                      .value_name("NAME")
+                      .help("Shape of the probe pressed into the top of the film: none, circle or cap")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("tip_radius")
+                      .long("tip_radius")
+                      .value_name("FLOAT")
+                      .help("Radius of the indenter tip")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("cap_height")
+                      .long("cap_height")
+                      .value_name("FLOAT")
+                      .help("Height of a cap tip, cut flat from the bottom of a circle of tip_radius")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("tip_x")
+                      .long("tip_x")
+                      .value_name("FLOAT")
+                      .help("x of the indenter tip")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("tip_y")
+                      .long("tip_y")
+                      .value_name("FLOAT")
+                      .help("Height of the lowest point of the tip before it moves in (0 for the top of the film)")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("indent_k")
+                      .long("indent_k")
+                      .value_name("FLOAT")
+                      .help("Contact stiffness between the tip and the cells")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("indent_path")
+                      .long("indent_path")
+                      .value_name("NAME")
+                      .help("How the tip moves in: ramp (down to indent_depth and held), triangle (down and back up, repeated) or table")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("indent_rate")
+                      .long("indent_rate")
+                      .value_name("FLOAT")
+                      .help("Speed the tip moves at for ramp and triangle paths")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("indent_depth")
+                      .long("indent_depth")
+                      .value_name("FLOAT")
+                      .help("Deepest the tip goes for ramp and triangle paths")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("indent_table")
+                      .long("indent_table")
+                      .value_name("FILE")
+                      .help("File of time depth rows for the table path")
+                      .takes_value(true)
+                    )
                     .arg(Arg::with_name("damping")
                       .long("damping")
                       .value_name("FLOAT")
@@ -82,7 +443,61 @@ fn main() {
                     .arg(Arg::with_name("extforce_x")
                       .long("extforce_x")
                       .value_name("FLOAT")
-                      .help("External force")
+                      .help("External force, also the final force of step and ramp")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("extforce_y")
+                      .long("extforce_y")
+                      .value_name("FLOAT")
+                      .help("External force along y for compress and biaxial")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("load_start")
+                      .long("load_start")
+                      .value_name("FLOAT")
+                      .help("Time step and ramp loading starts at")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("ramp_time")
+                      .long("ramp_time")
+                      .value_name("FLOAT")
+                      .help("Time ramp loading takes to reach extforce_x")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("chirp_omega")
+                      .long("chirp_omega")
+                      .value_name("FLOAT")
+                      .help("Omega a chirp reaches at del_t, starting from sineomega")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("multisine")
+                      .long("multisine")
+                      .value_name("AMP:OMEGA:PHASE,...")
+                      .help("Components of multisine loading")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("force_table")
+                      .long("force_table")
+                      .value_name("FILE")
+                      .help("File of time force rows for table loading, interpolated linearly")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("disp_rate")
+                      .long("disp_rate")
+                      .value_name("FLOAT")
+                      .help("Velocity of disp_ramp driven cells")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("disp_amp")
+                      .long("disp_amp")
+                      .value_name("FLOAT")
+                      .help("Amplitude of disp_sine driven cells, at sineomega")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("disp_table")
+                      .long("disp_table")
+                      .value_name("FILE")
+                      .help("File of time displacement rows for disp_table, interpolated linearly")
                       .takes_value(true)
                     )
                     .arg(Arg::with_name("lj_epsilon")
@@ -121,48 +536,81 @@ fn main() {
                       .help("Repulsion epsilon")
                       .takes_value(true)
                     )
+                    .arg(Arg::with_name("inertial")
+                      .long("inertial")
+                      .help("Give cells mass and velocity instead of overdamped motion")
+                    )
+                    .arg(Arg::with_name("mass")
+                      .long("mass")
+                      .value_name("FLOAT")
+                      .help("Mass of each cell for inertial grids")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("viscous")
+                      .long("viscous")
+                      .value_name("FLOAT")
+                      .help("Viscous damping coefficient for inertial grids")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("temperature")
+                      .long("temperature")
+                      .value_name("FLOAT")
+                      .help("Thermal noise strength for stochastic integrators")
+                      .takes_value(true)
+                    )
+                    .arg(Arg::with_name("seed")
+                      .long("seed")
+                      .value_name("INT")
+                      .help("Random number generator seed")
+                      .takes_value(true)
+                    )
                   )
-                  .subcommand(SubCommand::with_name("simulate")
+                  .subcommand(output_args(SubCommand::with_name("simulate")
                     .about("Simulate a grid")
                     .version("1.0")
-                    .author("Wyatt Campbell <wyatt.campbell@utexas.edu>")
-                    .arg(Arg::with_name("output")
-                      .long("output")
-                      .value_name("H.264 FILE")
-                      .help("Output directory")
+                    .author("Wyatt Campbell <wyatt.campbell@utexas.edu>"))
+                    .arg(Arg::with_name("integrator")
+                      .long("integrator")
+                      .value_name("NAME")
+                      .help("Time-stepping scheme (euler, rk, predictor_corrector, rk_adaptive, rk45, predictor_corrector_adaptive, verlet, euler_maruyama, backward_euler, bdf2, dormand_prince)")
                       .takes_value(true)
                     )
-                    .arg(Arg::with_name("avgstress")
-                      .long("avgstress")
-                      .value_name("PNG FILE")
-                      .help("File to output average stress vs time graph to")
+                    .arg(Arg::with_name("dt")
+                      .long("dt")
+                      .value_name("FLOAT")
+                      .help("Step size for fixed step integrators")
                       .takes_value(true)
                     )
-                    .arg(Arg::with_name("dist")
-                      .long("dist")
-                      .value_name("PNG FILE")
-                      .help("File to output average displacement vs time graph to")
+                    .arg(Arg::with_name("tol")
+                      .long("tol")
+                      .value_name("FLOAT")
+                      .help("Error tolerance for adaptive integrators")
                       .takes_value(true)
                     )
-                    .arg(Arg::with_name("xoff")
-                      .long("xoff")
-                      .value_name("PNG FILE")
-                      .help("File to output average x offset vs time graph to")
+                    .arg(Arg::with_name("dt_min")
+                      .long("dt_min")
+                      .value_name("FLOAT")
+                      .help("Minimum step size for adaptive integrators")
                       .takes_value(true)
                     )
-                    .arg(Arg::with_name("yoff")
-                      .long("yoff")
-                      .value_name("PNG FILE")
-                      .help("File to output average y offset vs time graph to")
+                    .arg(Arg::with_name("dt_max")
+                      .long("dt_max")
+                      .value_name("FLOAT")
+                      .help("Maximum step size for adaptive integrators")
                       .takes_value(true)
                     )
-                    .arg(Arg::with_name("stressstrain")
-                      .long("stressstrain")
-                      .value_name("PNG FILE")
-                      .help("File to output average stress vs average strain to")
+                    .arg(Arg::with_name("sample_dt")
+                      .long("sample_dt")
+                      .value_name("FLOAT")
+                      .help("Time between output samples for dormand_prince, defaults to one video frame")
                       .takes_value(true)
                     )
                   )
+                  .subcommand(output_args(SubCommand::with_name("resume")
+                    .about("Resume a simulation from its checkpoint")
+                    .version("1.0")
+                    .author("Wyatt Campbell <wyatt.campbell@utexas.edu>"))
+                  )
                   .get_matches();
 
   let grid_name = matches.value_of("grid").unwrap_or("grid.dat").to_string();
@@ -171,11 +619,114 @@ fn main() {
     generate(&grid_name[..], &matches);
   } else if let Some(matches) = matches.subcommand_matches("simulate") {
     simulate(&grid_name[..], &matches);
+  } else if let Some(matches) = matches.subcommand_matches("resume") {
+    resume(&grid_name[..], &matches);
   } else {
-    eprintln!("Need to choose `generate` or `simulate`.");
+    eprintln!("Need to choose `generate`, `simulate` or `resume`.");
   }
 }
 
+// Arguments shared by every subcommand that runs a simulation
+fn output_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+  app
+    .arg(Arg::with_name("output")
+      .long("output")
+      .value_name("H.264 FILE")
+      .help("Output directory")
+      .takes_value(true)
+    )
+    .arg(Arg::with_name("avgstress")
+      .long("avgstress")
+      .value_name("PNG FILE")
+      .help("File to output average stress vs time graph to")
+      .takes_value(true)
+    )
+    .arg(Arg::with_name("dist")
+      .long("dist")
+      .value_name("PNG FILE")
+      .help("File to output average displacement vs time graph to")
+      .takes_value(true)
+    )
+    .arg(Arg::with_name("xoff")
+      .long("xoff")
+      .value_name("PNG FILE")
+      .help("File to output average x offset vs time graph to")
+      .takes_value(true)
+    )
+    .arg(Arg::with_name("yoff")
+      .long("yoff")
+      .value_name("PNG FILE")
+      .help("File to output average y offset vs time graph to")
+      .takes_value(true)
+    )
+    .arg(Arg::with_name("stressstrain")
+      .long("stressstrain")
+      .value_name("PNG FILE")
+      .help("File to output average stress vs average strain to")
+      .takes_value(true)
+    )
+    .arg(Arg::with_name("msd")
+      .long("msd")
+      .value_name("PNG FILE")
+      .help("File to output mean squared displacement vs time graph to")
+      .takes_value(true)
+    )
+    .arg(Arg::with_name("reaction")
+      .long("reaction")
+      .value_name("PNG FILE")
+      .help("File to output reaction force vs displacement of displacement driven cells to")
+      .takes_value(true)
+    )
+    .arg(Arg::with_name("bonds")
+      .long("bonds")
+      .value_name("PNG FILE")
+      .help("File to output the number of broken bonds vs time graph to")
+      .takes_value(true)
+    )
+    .arg(Arg::with_name("shear")
+      .long("shear")
+      .value_name("PNG FILE")
+      .help("File to output bulk shear stress vs applied Lees-Edwards strain to")
+      .takes_value(true)
+    )
+    .arg(Arg::with_name("active")
+      .long("active")
+      .value_name("PNG FILE")
+      .help("File to output the active stress vs time graph to")
+      .takes_value(true)
+    )
+    .arg(Arg::with_name("detached")
+      .long("detached")
+      .value_name("PNG FILE")
+      .help("File to output the number of detached cells vs time graph to")
+      .takes_value(true)
+    )
+    .arg(Arg::with_name("indentation")
+      .long("indentation")
+      .value_name("PNG FILE")
+      .help("File to output the force on the indenter vs its depth graph to")
+      .takes_value(true)
+    )
+    .arg(Arg::with_name("indentation_data")
+      .long("indentation_data")
+      .value_name("FILE")
+      .help("File to write time, depth and force on the indenter rows to, for fitting")
+      .takes_value(true)
+    )
+    .arg(Arg::with_name("trajectory")
+      .long("trajectory")
+      .value_name("FILE")
+      .help("File to stream every step of the trajectory to")
+      .takes_value(true)
+    )
+    .arg(Arg::with_name("checkpoint")
+      .long("checkpoint")
+      .value_name("FLOAT")
+      .help("Simulation time between checkpoints written next to the grid")
+      .takes_value(true)
+    )
+}
+
 fn generate(grid_name: &str, matches: &clap::ArgMatches) {
   let mut settings = settings::Settings::new();
   if let Some(error) = settings.args(&matches) {
@@ -197,15 +748,37 @@ fn generate(grid_name: &str, matches: &clap::ArgMatches) {
 
   let fixed = matches.value_of("fixed").unwrap_or("none").to_string().to_lowercase();
   let updatefunc = update::func_enum(&fixed[..]);
+  if updatefunc == update::UpdateFunc::MultiSine && settings.multisine.is_empty() {
+    eprintln!("multisine loading needs --multisine");
+    return;
+  }
+  if updatefunc == update::UpdateFunc::Table && settings.force_table.is_empty() {
+    eprintln!("table loading needs --force_table");
+    return;
+  }
+  if updatefunc == update::UpdateFunc::DispTable && settings.disp_table.is_empty() {
+    eprintln!("disp_table loading needs --disp_table");
+    return;
+  }
+  if settings.wall == forces::Wall::Polygon && settings.wall_polygon.is_empty() {
+    eprintln!("polygon walls need --wall_polygon");
+    return;
+  }
   let major_hook = update::enum_major(&updatefunc);
   let minor_hook = update::enum_minor(&updatefunc);
 
-  let grid = generation::generate_offsetgrid(
+  let grid = match generation::generate_offsetgrid(
       &mut settings,
       size,
       major_hook,
       minor_hook
-    ).unwrap();
+    ) {
+    Ok(grid) => grid,
+    Err(error) => {
+      eprintln!("Error: {}", error);
+      return;
+    }
+  };
 
   let settings_ron = ron::to_string(&settings).expect("RONification failed");
   let ron = ron::to_string(&grid).expect("RONification failed");
@@ -224,68 +797,433 @@ fn simulate(grid_name: &str, matches: &clap::ArgMatches) {
   }
 
   let settings: settings::Settings = ron::from_str(&lines[0][..]).expect("deRONification failed");
-  let grid: Vec<cell::Cell> = ron::from_str(&lines[1][..]).expect("deRONification failed");
-
-  //let mut states = simulation::rk45(&grid, 0.01, 0.001, 0.1, simulation::derivs, &settings);
-  //let mut states = simulation::predictor_corrector(&grid, 0.01, simulation::derivs, &settings);
-  let mut states = simulation::predictor_corrector_adaptive(&grid, 0.01, 0.001, 0.1, simulation::derivs, &settings);
-
-  let stress: Vec<_> = states.par_iter_mut()
-    .map(|tuple| {
-      let time = tuple.1;
-      let state = &mut tuple.2;
-      let avgs = simulation::get_stress(state, time, &settings);
-      if -avgs.max_tension > avgs.max_compression {
-        (-avgs.max_tension, avgs)
-      } else {
-        (avgs.max_compression, avgs)
+  let mut grid: Vec<cell::Cell> = ron::from_str(&lines[1][..]).expect("deRONification failed");
+  if grid.iter().all(|cell| cell.links.is_empty()) {
+    generation::link_neighbors(&mut grid, &settings);
+  }
+
+  let default = if settings.inertial { "verlet" } else { "predictor_corrector_adaptive" };
+  let name = matches.value_of("integrator").unwrap_or(default).to_string().to_lowercase();
+  let func = match integrator::func_enum(&name[..]) {
+    Some(func) => func,
+    None => {
+      eprintln!("Unknown integrator {}", name);
+      return;
+    }
+  };
+  if func.inertial() != settings.inertial {
+    if settings.inertial {
+      eprintln!("Inertial grids need an inertial integrator such as verlet");
+    } else {
+      eprintln!("{} needs a grid generated with --inertial", name);
+    }
+    return;
+  }
+  if settings.temperature > 0.0 && !func.stochastic() {
+    eprintln!("Warning: {} ignores temperature, use euler_maruyama for thermal noise", name);
+  }
+
+  let mut params = integrator::IntegratorParams::new();
+  if let Some(error) = params.args(&matches) {
+    eprintln!("Error: {}", error);
+    return;
+  }
+
+  let trajectory = match matches.value_of("trajectory") {
+    Some(name) => match integrator::TrajectoryWriter::new(name) {
+      Ok(writer) => Some(writer),
+      Err(error) => {
+        eprintln!("Error: {}", error);
+        return;
+      }
+    },
+    None => None
+  };
+
+  let encoder = Encoder::new(matches.value_of("output").unwrap_or("output.h264"));
+  let series = SeriesWriter::new(&series_name(grid_name));
+
+  let start = integrator::Checkpoint { field: nutrient::Field::solve(&grid, &settings), ..integrator::Checkpoint::new(&grid) };
+  run(grid_name, matches, &settings, func, params, start, trajectory, encoder, series, Progress::new());
+}
+
+fn resume(grid_name: &str, matches: &clap::ArgMatches) {
+  let file = File::open(checkpoint_name(grid_name)).expect("Failed to open checkpoint");
+  let buffered = BufReader::new(file);
+  let mut lines: Vec<String> = vec![];
+
+  for line in buffered.lines() {
+    lines.push(line.unwrap());
+  }
+
+  let settings: settings::Settings = ron::from_str(&lines[0][..]).expect("deRONification failed");
+  let (func, params): (integrator::IntegratorFunc, integrator::IntegratorParams) =
+    ron::from_str(&lines[1][..]).expect("deRONification failed");
+  let start: integrator::Checkpoint = ron::from_str(&lines[2][..]).expect("deRONification failed");
+  let progress: Progress = ron::from_str(&lines[3][..]).expect("deRONification failed");
+
+  let trajectory = match matches.value_of("trajectory") {
+    Some(name) => match integrator::TrajectoryWriter::resume(name, start.iter) {
+      Ok(writer) => Some(writer),
+      Err(error) => {
+        eprintln!("Error: {}", error);
+        return;
       }
-    }).collect();
-  let max_stress = stress.iter().max_by(|f1, f2| f1.0.partial_cmp(&f2.0).unwrap()).unwrap().0;
-  let max_stress = if max_stress <= 1e-10 { 1.0 } else { max_stress };
+    },
+    None => None
+  };
 
-  let output = matches.value_of("output").unwrap_or("output.h264").to_string();
+  let encoder = Encoder::resume(matches.value_of("output").unwrap_or("output.h264"), &progress);
+  let series = SeriesWriter::resume(&series_name(grid_name), &progress);
 
-  encode(&states, &output, max_stress);
+  run(grid_name, matches, &settings, func, params, start, trajectory, encoder, series, progress);
+}
+
+fn checkpoint_name(grid_name: &str) -> String {
+  format!("{}.checkpoint", grid_name)
+}
+
+fn series_name(grid_name: &str) -> String {
+  format!("{}.series", grid_name)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run(
+  grid_name: &str,
+  matches: &clap::ArgMatches,
+  settings: &settings::Settings,
+  func: integrator::IntegratorFunc,
+  params: integrator::IntegratorParams,
+  start: integrator::Checkpoint,
+  trajectory: Option<integrator::TrajectoryWriter>,
+  encoder: Encoder,
+  series: SeriesWriter,
+  progress: Progress
+) {
+  let checkpoint_every = match matches.value_of("checkpoint") {
+    Some(every) => match every.parse::<f64>() {
+      Ok(every) if every > 0.0 => Some(every),
+      _ => {
+        eprintln!("checkpoint must be a positive number");
+        return;
+      }
+    },
+    None => None
+  };
+
+  let mut recorder = Recorder {
+    settings,
+    func,
+    params,
+    trajectory,
+    encoder,
+    series,
+    progress,
+    checkpoint_name: checkpoint_name(grid_name),
+    checkpoint_every,
+    next_checkpoint: start.time
+  };
+  if let Some(every) = checkpoint_every {
+    recorder.next_checkpoint += every;
+  }
+
+  // A failed run still leaves the trajectory, video and graphs up to the failure
+  let integrator = integrator::integrator(&func, &params);
+  if let Err(error) = integrator.integrate(start, simulation::derivs, settings, &mut recorder) {
+    eprintln!("Error: simulation stopped early, {}", error);
+  }
+  if let Some(trajectory) = &mut recorder.trajectory {
+    if trajectory.flush().is_err() {
+      eprintln!("Error: writing the trajectory failed");
+    }
+  }
+  if recorder.encoder.finish().is_err() {
+    eprintln!("Error: writing the video failed");
+  }
+
+  let series = match recorder.series.read() {
+    Ok(series) => series,
+    Err(_e) => {
+      eprintln!("Error: reading back the series failed, so there are no graphs");
+      return;
+    }
+  };
+  if series.is_empty() {
+    eprintln!("No steps were recorded, so there are no graphs");
+    return;
+  }
 
   if let Some(avgstress) = matches.value_of("avgstress") {
-    let stress: Vec<_> = states.iter().enumerate().map(|(ind, (_iter, time, _state))| {
-        (*time, stress[ind].1.avg_stress)
-    }).collect();
+    let stress: Vec<_> = series.iter().map(|s| (s.time, s.avg_stress)).collect();
     gfx::plot_avgstress(&stress, avgstress);
   }
 
-  let strain: Vec<_> = states.iter_mut().map(|tuple| {
-    simulation::get_strain(&mut tuple.2, tuple.1)
-  }).collect();
-
   if let Some(disp) = matches.value_of("dist") {
-    let strain: Vec<_> = strain.iter().enumerate().map(|(ind, strain)| {
-      (states[ind].1, strain.avgstrain.norm())
-    }).collect();
+    let strain: Vec<_> = series.iter().map(|s| (s.time, s.avg_strain.norm())).collect();
     gfx::plot_dist(&strain, disp);
   }
 
   if let Some(xoff) = matches.value_of("xoff") {
-    let strain: Vec<_> = strain.iter().enumerate().map(|(ind, strain)| {
-      (states[ind].1, strain.avgstrain.x)
-    }).collect();
+    let strain: Vec<_> = series.iter().map(|s| (s.time, s.avg_strain.x)).collect();
     gfx::plot_dist(&strain, xoff);
   }
 
   if let Some(yoff) = matches.value_of("yoff") {
-    let strain: Vec<_> = strain.iter().enumerate().map(|(ind, strain)| {
-      (states[ind].1, strain.avgstrain.y)
-    }).collect();
+    let strain: Vec<_> = series.iter().map(|s| (s.time, s.avg_strain.y)).collect();
     gfx::plot_dist(&strain, yoff);
   }
 
   if let Some(stressstrain) = matches.value_of("stressstrain") {
-    let strain: Vec<_> = strain.iter().enumerate().map(|(ind, strain)| {
-      (stress[ind].1.avg_stress, strain.avgstrain.norm())
-    }).collect();
+    let strain: Vec<_> = series.iter().map(|s| (s.avg_stress, s.avg_strain.norm())).collect();
     gfx::plot_stressstrain(&strain, stressstrain);
   }
+
+  if let Some(name) = matches.value_of("msd") {
+    let msd: Vec<_> = series.iter().map(|s| (s.time, s.msd)).collect();
+    gfx::plot_msd(&msd, name);
+  }
+
+  if let Some(name) = matches.value_of("reaction") {
+    let reaction: Vec<_> = series.iter().map(|s| (s.displacement.x, s.reaction.x)).collect();
+    gfx::plot_reaction(&reaction, name);
+  }
+
+  if let Some(name) = matches.value_of("bonds") {
+    let broken: Vec<_> = series.iter().map(|s| (s.time, s.broken as f64)).collect();
+    gfx::plot_bonds(&broken, name);
+  }
+
+  if let Some(name) = matches.value_of("shear") {
+    let shear: Vec<_> = series.iter().map(|s| (s.shear_strain, s.shear_stress)).collect();
+    gfx::plot_shear(&shear, name);
+  }
+
+  if let Some(name) = matches.value_of("active") {
+    let active: Vec<_> = series.iter().map(|s| (s.time, s.active_stress)).collect();
+    gfx::plot_active(&active, name);
+  }
+
+  if let Some(name) = matches.value_of("detached") {
+    let detached: Vec<_> = series.iter().map(|s| (s.time, s.detached as f64)).collect();
+    gfx::plot_detached(&detached, name);
+  }
+
+  if let Some(name) = matches.value_of("indentation") {
+    let indentation: Vec<_> = series.iter().map(|s| (s.indent_depth, s.indent_force)).collect();
+    gfx::plot_indentation(&indentation, name);
+  }
+
+  if let Some(name) = matches.value_of("indentation_data") {
+    let mut file = File::create(name).expect("File creation failed");
+    writeln!(file, "# time depth force").expect("File writing failed");
+    for sample in &series {
+      writeln!(file, "{} {} {}", sample.time, sample.indent_depth, sample.indent_force).expect("File writing failed");
+    }
+  }
+}
+
+// Averages of a single step
+#[derive(Serialize, Deserialize)]
+struct Sample {
+  time: f64,
+  avg_stress: f64,
+  avg_strain: cell::Pos,
+  msd: f64,
+  #[serde(default)]
+  reaction: cell::Pos,
+  #[serde(default)]
+  displacement: cell::Pos,
+  #[serde(default)]
+  bonds: usize,
+  #[serde(default)]
+  broken: usize,
+  #[serde(default)]
+  shear_strain: f64,
+  #[serde(default)]
+  shear_stress: f64,
+  #[serde(default)]
+  active_stress: f64,
+  #[serde(default)]
+  detached: usize,
+  #[serde(default)]
+  indent_depth: f64,
+  #[serde(default)]
+  indent_force: f64
+}
+
+// Running totals of a simulation, saved with checkpoints so the video and graphs cover
+// resumed runs too
+#[derive(Serialize, Deserialize)]
+struct Progress {
+  max_stress: f64,
+  frames: i64,
+  timestamp: i64, // of the next frame
+  video_bytes: u64, // of the output written by the checkpoint
+  series_bytes: u64 // of the series written by the checkpoint
+}
+
+impl Progress {
+  fn new() -> Progress {
+    Progress {
+      max_stress: 0.0,
+      frames: 0,
+      timestamp: 0,
+      video_bytes: 0,
+      series_bytes: 0
+    }
+  }
+}
+
+// Writes the sample of each step as a line of the series file next to the grid, so
+// checkpoints only need to keep how much of it they cover
+struct SeriesWriter {
+  name: String,
+  file: BufWriter<File>
+}
+
+impl SeriesWriter {
+  fn new(name: &str) -> SeriesWriter {
+    let file = File::create(name).expect("Unable to open series file");
+    SeriesWriter { name: name.to_string(), file: BufWriter::new(file) }
+  }
+
+  // Continue the series of a checkpoint, dropping anything written after it
+  fn resume(name: &str, progress: &Progress) -> SeriesWriter {
+    let file = OpenOptions::new().append(true).open(name).expect("Unable to open series file");
+    file.set_len(progress.series_bytes).expect("Unable to open series file");
+    SeriesWriter { name: name.to_string(), file: BufWriter::new(file) }
+  }
+
+  fn push(&mut self, sample: &Sample) -> io::Result<()> {
+    writeln!(self.file, "{}", to_ron(sample)?)
+  }
+
+  // Puts every sample so far on disk. Returns the length of the file.
+  fn checkpoint(&mut self) -> io::Result<u64> {
+    self.file.flush()?;
+    self.file.get_ref().sync_all()?;
+    Ok(self.file.get_ref().metadata()?.len())
+  }
+
+  // Every sample of the run, for the graphs
+  fn read(mut self) -> io::Result<Vec<Sample>> {
+    self.file.flush()?;
+    let file = File::open(&self.name)?;
+    BufReader::new(file).lines().map(|line| {
+      ron::from_str(&line?).map_err(|_e| io::Error::from(io::ErrorKind::InvalidData))
+    }).collect()
+  }
+}
+
+// Computes stress and strain of each step as it is produced and encodes frames on the fly,
+// writing out the scalar averages to the series file
+struct Recorder<'a> {
+  settings: &'a settings::Settings,
+  func: integrator::IntegratorFunc,
+  params: integrator::IntegratorParams,
+  trajectory: Option<integrator::TrajectoryWriter>,
+  encoder: Encoder,
+  series: SeriesWriter,
+  progress: Progress,
+  checkpoint_name: String,
+  checkpoint_every: Option<f64>,
+  next_checkpoint: f64
+}
+
+impl<'a> integrator::Observer for Recorder<'a> {
+  fn observe(&mut self, iter: i32, time: f64, state: &[cell::Cell], field: Option<&nutrient::Field>) -> Result<(), integrator::IntegratorError> {
+    if let Some(trajectory) = &mut self.trajectory {
+      trajectory.observe(iter, time, state, field)?;
+    }
+
+    let mut state = state.to_vec();
+    let avgs = simulation::get_stress(&mut state, time, self.settings)?;
+    let strain = simulation::get_strain(&mut state, time);
+    let reaction = simulation::get_reaction(&state, time, self.settings)?;
+    let indentation = match indenter::tip(time, self.settings) {
+      Some(tip) => (tip.depth, indenter::reaction(&state, time, self.settings)),
+      None => (0.0, 0.0)
+    };
+
+    // Colors are scaled by the largest stress seen so far, since later steps are not known yet
+    let progress = &mut self.progress;
+    if -avgs.max_tension > progress.max_stress {
+      progress.max_stress = -avgs.max_tension;
+    }
+    if avgs.max_compression > progress.max_stress {
+      progress.max_stress = avgs.max_compression;
+    }
+    let max_stress = if progress.max_stress <= 1e-10 { 1.0 } else { progress.max_stress };
+
+    if self.settings.periodic_x || self.settings.periodic_y {
+      simulation::wrap_positions(&mut state, time, self.settings);
+    }
+    let sample = Sample {
+      time,
+      avg_stress: avgs.avg_stress,
+      avg_strain: strain.avgstrain,
+      msd: strain.msd,
+      reaction: reaction.force,
+      displacement: reaction.displacement,
+      bonds: state.iter().map(|c| c.links.len()).sum::<usize>() / 2,
+      broken: state.iter().map(|c| c.broken).sum::<usize>() / 2,
+      shear_strain: forces::applied_shear(time, self.settings),
+      shear_stress: avgs.shear_xy,
+      active_stress: avgs.active_stress,
+      detached: state.iter().filter(|c| c.detached.is_some()).count(),
+      indent_depth: indentation.0,
+      indent_force: indentation.1
+    };
+    self.encoder.frame(time, &state, field, max_stress, self.settings)
+      .and_then(|_| self.series.push(&sample))
+      .map_err(|e| integrator::IntegratorError::io(time, e))
+  }
+
+  fn wants_checkpoint(&mut self, time: f64) -> bool {
+    match self.checkpoint_every {
+      Some(_) => time >= self.next_checkpoint,
+      None => false
+    }
+  }
+
+  fn checkpoint(&mut self, checkpoint: &integrator::Checkpoint) -> Result<(), integrator::IntegratorError> {
+    if let Some(every) = self.checkpoint_every {
+      while self.next_checkpoint <= checkpoint.time {
+        self.next_checkpoint += every;
+      }
+    }
+
+    if let Some(trajectory) = &mut self.trajectory {
+      trajectory.checkpoint(checkpoint)?;
+    }
+    self.write_checkpoint(checkpoint).map_err(|e| integrator::IntegratorError::io(checkpoint.time, e))
+  }
+}
+
+impl<'a> Recorder<'a> {
+  fn write_checkpoint(&mut self, checkpoint: &integrator::Checkpoint) -> io::Result<()> {
+    self.progress.video_bytes = self.encoder.checkpoint()?;
+    self.progress.frames = self.encoder.frame;
+    self.progress.timestamp = self.encoder.timestamp;
+    self.progress.series_bytes = self.series.checkpoint()?;
+
+    let settings_ron = to_ron(self.settings)?;
+    let integrator_ron = to_ron(&(self.func, self.params))?;
+    let checkpoint_ron = to_ron(checkpoint)?;
+    let progress_ron = to_ron(&self.progress)?;
+
+    // Write to the side first so being killed mid-write keeps the previous checkpoint intact
+    let tmp_name = format!("{}.tmp", self.checkpoint_name);
+    let mut file = File::create(&tmp_name)?;
+    write!(file, "{}\n{}\n{}\n{}", settings_ron, integrator_ron, checkpoint_ron, progress_ron)?;
+    file.sync_all()?;
+    std::fs::rename(&tmp_name, &self.checkpoint_name)
+  }
+}
+
+// RON of value, failing as I/O does so it can be handled along with the writing
+fn to_ron<T: Serialize>(value: &T) -> io::Result<String> {
+  ron::to_string(value).map_err(|_e| io::Error::from(io::ErrorKind::InvalidData))
 }
 
 fn to_i420(frame: &[u8]) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
@@ -321,7 +1259,17 @@ fn to_i420(frame: &[u8]) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
   (y_plane, u_plane, v_plane)
 }
 
-fn encode(states: &[(i32, f64, Vec<cell::Cell>)], output: &str, max_stress: f64) {
+struct Encoder {
+  pic: Option<x264::Picture>,
+  enc: x264::Encoder,
+  output: File,
+  timestamp: i64,
+  frame: i64 // frame whose time, frame / FPS, is the next to be shown
+}
+
+// Opens an x264 stream. Headers are repeated in annex B form, so streams written one after
+// another into a file play as one video.
+fn open_stream() -> (x264::Picture, x264::Encoder) {
   let mut par = x264::Param::new();
   par = par.set_dimension(gfx::SIZE, gfx::SIZE);
   par = par.param_parse("repeat_headers", "1").unwrap();
@@ -329,45 +1277,84 @@ fn encode(states: &[(i32, f64, Vec<cell::Cell>)], output: &str, max_stress: f64)
   par = par.param_parse("fps", &gfx::FPS.to_string()).unwrap();
   par = par.apply_profile("high").unwrap();
 
-  let mut pic = x264::Picture::from_param(&par).unwrap();
+  let pic = x264::Picture::from_param(&par).unwrap();
+  let enc = x264::Encoder::open(&mut par).unwrap();
+  (pic, enc)
+}
+
+impl Encoder {
+  fn new(output: &str) -> Encoder {
+    let (pic, enc) = open_stream();
+    let output = File::create(output).expect("Unable to open output file");
 
-  let mut enc = x264::Encoder::open(&mut par).unwrap();
-  let mut output = File::create(output).expect("Unable to open output file");
-  let mut timestamp = 0;
+    Encoder {
+      pic: Some(pic),
+      enc,
+      output,
+      timestamp: 0,
+      frame: 0
+    }
+  }
 
-  let mut acc = 0.0;
-  let mut frames: Vec<Vec<cell::Cell>> = vec![];
-  let mut last_time = 0.0;
-  for (_, t, state) in states {
-    let dt = *t - last_time;
-    acc += dt;
+  // Continue the video of a checkpoint, dropping anything written after it
+  fn resume(output: &str, progress: &Progress) -> Encoder {
+    let (pic, enc) = open_stream();
+    let output = OpenOptions::new().append(true).open(output).expect("Unable to open output file");
+    output.set_len(progress.video_bytes).expect("Unable to open output file");
 
-    if acc > 1.0/(gfx::FPS as f64) {
-      frames.push(state.clone());
-      acc -= 1.0 / (gfx::FPS as f64);
+    Encoder {
+      pic: Some(pic),
+      enc,
+      output,
+      timestamp: progress.timestamp,
+      frame: progress.frames
     }
+  }
 
-    last_time = *t;
+  // Writes out the frames x264 still holds and starts a new stream after them, so the file
+  // has every frame up to here. Returns the length of the file.
+  fn checkpoint(&mut self) -> io::Result<u64> {
+    self.finish()?;
+    let (pic, enc) = open_stream();
+    self.pic = Some(pic);
+    self.enc = enc;
+    self.output.sync_all()?;
+    Ok(self.output.metadata()?.len())
   }
 
-  for frame in frames {
-    let frame = to_i420(&gfx::plot_buf(&frame, max_stress));
+  // Encode the state if it is the first one at or after the next frame time. Frames sit
+  // on exact multiples of 1 / FPS, so states sampled at those times all become frames.
+  fn frame(&mut self, t: f64, state: &[cell::Cell], field: Option<&nutrient::Field>, max_stress: f64, settings: &settings::Settings) -> io::Result<()> {
+    let frame_dt = 1.0 / (gfx::FPS as f64);
+    if t < (self.frame as f64 - 1e-6) * frame_dt {
+      return Ok(());
+    }
+    self.frame = (t / frame_dt + 1e-6).floor() as i64 + 1;
+
+    let tip = indenter::tip(t, settings);
+    let frame = to_i420(&gfx::plot_buf(state, max_stress, field, tip.as_ref()));
+    let mut pic = self.pic.take().unwrap();
     pic.as_mut_slice(0).unwrap().copy_from_slice(&frame.0);
     pic.as_mut_slice(1).unwrap().copy_from_slice(&frame.1);
     pic.as_mut_slice(2).unwrap().copy_from_slice(&frame.2);
 
-    pic = pic.set_timestamp(timestamp);
-    timestamp += 1;
-    if let Some((nal, _, _)) = enc.encode(&pic).unwrap() {
+    pic = pic.set_timestamp(self.timestamp);
+    self.timestamp += 1;
+    if let Some((nal, _, _)) = self.enc.encode(&pic).unwrap() {
       let buf = nal.as_bytes();
-      output.write_all(buf).unwrap();
+      self.output.write_all(buf)?;
     }
+    self.pic = Some(pic);
+    Ok(())
   }
 
-  while enc.delayed_frames() {
-    if let Some((nal, _, _)) = enc.encode(None).unwrap() {
-      let buf = nal.as_bytes();
-      output.write_all(buf).unwrap();
+  fn finish(&mut self) -> io::Result<()> {
+    while self.enc.delayed_frames() {
+      if let Some((nal, _, _)) = self.enc.encode(None).unwrap() {
+        let buf = nal.as_bytes();
+        self.output.write_all(buf)?;
+      }
     }
+    Ok(())
   }
 }
@@ -0,0 +1,34 @@
+use serde::{Serialize, Deserialize};
+
+// Small seedable generator (splitmix64) whose whole state is one u64,
+// so it can be stored in checkpoints and runs stay reproducible
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct Rng {
+  state: u64
+}
+
+impl Rng {
+  pub fn new(seed: u64) -> Rng {
+    Rng { state: seed }
+  }
+
+  pub fn next_u64(&mut self) -> u64 {
+    self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = self.state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+  }
+
+  // Uniform in (0, 1]
+  pub fn uniform(&mut self) -> f64 {
+    ((self.next_u64() >> 11) + 1) as f64 / (1u64 << 53) as f64
+  }
+
+  // Standard normal by Box-Muller
+  pub fn gaussian(&mut self) -> f64 {
+    let u1 = self.uniform();
+    let u2 = self.uniform();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+  }
+}
@@ -0,0 +1,194 @@
+use serde::{Serialize, Deserialize};
+use crate::{cell, settings, simulation};
+
+// Relative residual and iteration cap of the field solve
+const FIELD_TOL: f64 = 1e-8;
+const FIELD_ITER: usize = 1000;
+
+// Where nutrient enters the domain. Supplied edges are held at concentration 1 and the
+// others let nothing through.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Supply {
+  None, // no nutrient field
+  Edges, // every edge that isn't periodic
+  Bottom, // the substrate along y = 0
+  Top, // the medium along y = 1
+}
+
+pub fn supply_enum(name: &str) -> Option<Supply> {
+  match name {
+    "none" => Some(Supply::None),
+    "edges" => Some(Supply::Edges),
+    "bottom" => Some(Supply::Bottom),
+    "top" => Some(Supply::Top),
+    _ => None
+  }
+}
+
+// Nutrient concentration on a res by res grid of squares over the unit box, in units of the
+// supply concentration. Values are at the centers of the squares, row by row from y = 0.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Field {
+  pub res: usize,
+  pub conc: Vec<f64>,
+}
+
+impl Field {
+  // Steady state of the field for the cells where they are now, which it starts out in.
+  // Returns None without a supply.
+  pub fn solve(grid: &[cell::Cell], settings: &settings::Settings) -> Option<Field> {
+    if settings.nutrient_supply == Supply::None {
+      return None;
+    }
+    Some(Field::implicit(grid, None, settings))
+  }
+
+  // Advances the field over dt by an implicit Euler step of
+  //   dc/dt = nutrient_diffusion * laplacian(c) - nutrient_uptake * (area of cells per area) * c
+  // for the cells where they are now
+  pub fn step(&mut self, grid: &[cell::Cell], dt: f64, settings: &settings::Settings) {
+    if dt <= 0.0 {
+      return;
+    }
+    *self = Field::implicit(grid, Some((self, dt)), settings);
+  }
+
+  // Solves the field after dt from previous, or the steady state without one
+  fn implicit(grid: &[cell::Cell], previous: Option<(&Field, f64)>, settings: &settings::Settings) -> Field {
+    let res = settings.nutrient_res;
+
+    // Uptake of each square relative to diffusion between squares
+    let mut uptake = vec![0.0; res * res];
+    for cell in grid {
+      let square = Field::square(res, &cell.pos, settings);
+      uptake[square] += settings.nutrient_uptake * std::f64::consts::PI * cell.radius * cell.radius
+        / settings.nutrient_diffusion;
+    }
+
+    // What is across each side of a square
+    let supplied = |side: Side| match settings.nutrient_supply {
+      Supply::None => false,
+      Supply::Edges => match side {
+        Side::Left | Side::Right => !settings.periodic_x,
+        Side::Bottom | Side::Top => !settings.periodic_y,
+      },
+      Supply::Bottom => side == Side::Bottom,
+      Supply::Top => side == Side::Top,
+    };
+    let across = |row: usize, col: usize, side: Side| -> Across {
+      let (periodic, at_edge) = match side {
+        Side::Left => (settings.periodic_x, col == 0),
+        Side::Right => (settings.periodic_x, col == res - 1),
+        Side::Bottom => (settings.periodic_y, row == 0),
+        Side::Top => (settings.periodic_y, row == res - 1),
+      };
+      if at_edge && !periodic {
+        return if supplied(side) { Across::Supplied } else { Across::Closed };
+      }
+      Across::Square(match side {
+        Side::Left => row * res + (col + res - 1) % res,
+        Side::Right => row * res + (col + 1) % res,
+        Side::Bottom => ((row + res - 1) % res) * res + col,
+        Side::Top => ((row + 1) % res) * res + col,
+      })
+    };
+
+    // Supplied edges are half a square away and held at 1, closed ones pass no flux
+    let mut b = vec![0.0; res * res];
+    let mut diagonal = uptake;
+    for k in 0..res * res {
+      for side in SIDES.iter() {
+        match across(k / res, k % res, *side) {
+          Across::Square(_) => diagonal[k] += 1.0,
+          Across::Supplied => {
+            diagonal[k] += 2.0;
+            b[k] += 2.0;
+          },
+          Across::Closed => ()
+        }
+      }
+    }
+
+    // The concentration a step starts from holds the new one back by the time the nutrient
+    // takes to diffuse across a square
+    if let Some((field, dt)) = previous {
+      let side = simulation::PERIOD / res as f64;
+      let hold = side * side / (settings.nutrient_diffusion * dt);
+      for k in 0..res * res {
+        diagonal[k] += hold;
+        b[k] += hold * field.conc[k];
+      }
+    }
+
+    let apply = |c: &[f64]| -> Result<Vec<f64>, std::convert::Infallible> {
+      Ok((0..res * res).map(|k| {
+        let coupled: f64 = SIDES.iter().map(|side| match across(k / res, k % res, *side) {
+          Across::Square(n) => c[n],
+          _ => 0.0
+        }).sum();
+        diagonal[k] * c[k] - coupled
+      }).collect())
+    };
+    let conc = match simulation::bicgstab(apply, &b, FIELD_TOL, FIELD_ITER) {
+      Ok(conc) => conc,
+      Err(never) => match never {}
+    };
+
+    Field { res, conc: conc.iter().map(|c| c.clamp(0.0, 1.0)).collect() }
+  }
+
+  // Gives every cell the concentration where it sits. Returns whether any of them changed.
+  pub fn apply(&self, grid: &mut [cell::Cell], settings: &settings::Settings) -> bool {
+    let mut changed = false;
+    for cell in grid.iter_mut() {
+      let nutrient = self.at(&cell.pos, settings);
+      changed |= nutrient != cell.nutrient;
+      cell.nutrient = nutrient;
+    }
+    changed
+  }
+
+  // Square holding pos. Periodic axes wrap, cells past a closed edge count in the edge square.
+  fn square(res: usize, pos: &cell::Pos, settings: &settings::Settings) -> usize {
+    let index = |x: f64, periodic: bool| {
+      let x = if periodic { x.rem_euclid(simulation::PERIOD) } else { x };
+      ((x / simulation::PERIOD * res as f64).floor().max(0.0) as usize).min(res - 1)
+    };
+    index(pos.y, settings.periodic_y) * res + index(pos.x, settings.periodic_x)
+  }
+
+  // Concentration at pos, from the square holding it
+  pub fn at(&self, pos: &cell::Pos, settings: &settings::Settings) -> f64 {
+    self.conc[Field::square(self.res, pos, settings)]
+  }
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+enum Side {
+  Left,
+  Right,
+  Bottom,
+  Top,
+}
+
+const SIDES: [Side; 4] = [Side::Left, Side::Right, Side::Bottom, Side::Top];
+
+enum Across {
+  Square(usize),
+  Supplied,
+  Closed,
+}
+
+// Advances the field over dt, starting it from its steady state if there is none yet, and
+// gives every cell the concentration where it sits. Nutrient only acts on the forces through
+// nutrient_stiffness, so returns whether the forces changed that way.
+pub fn feed(grid: &mut [cell::Cell], field: &mut Option<Field>, dt: f64, settings: &settings::Settings) -> bool {
+  match field {
+    Some(field) => field.step(grid, dt, settings),
+    None => *field = Field::solve(grid, settings),
+  }
+  match field {
+    Some(field) => field.apply(grid, settings) && settings.nutrient_stiffness != 0.0,
+    None => false
+  }
+}
@@ -1,5 +1,9 @@
+// Leaves this small keep every item they are given instead of splitting, so items at the
+// same position don't split forever
+const MIN_SPAN: f64 = 1e-9;
+
 pub struct QuadTree<T: Clone> {
-  data: Option<T>,
+  items: Vec<(T, f64, f64)>,
   q1: Option<Box<QuadTree<T>>>,
   q2: Option<Box<QuadTree<T>>>,
   q3: Option<Box<QuadTree<T>>>,
@@ -9,8 +13,8 @@ pub struct QuadTree<T: Clone> {
   xmax: f64,
   ymin: f64,
   ymax: f64,
-  x: f64,
-  y: f64,
+  wrap_x: bool,
+  wrap_y: bool,
 }
 
 impl<T: Clone> QuadTree<T> {
@@ -24,7 +28,7 @@ impl<T: Clone> QuadTree<T> {
     }
 
     QuadTree{
-      data: None,
+      items: vec![],
       q1: None,
       q2: None,
       q3: None,
@@ -34,12 +38,22 @@ impl<T: Clone> QuadTree<T> {
       xmax,
       ymin,
       ymax,
-      x: 0.0,
-      y: 0.0,
+      wrap_x: false,
+      wrap_y: false,
     }
   }
 
+  // Makes the bounds periodic in x and/or y. Items added outside of a periodic axis are
+  // wrapped back in and queries also find items across the wrap.
+  pub fn wrapping(mut self, wrap_x: bool, wrap_y: bool) -> QuadTree<T> {
+    self.wrap_x = wrap_x;
+    self.wrap_y = wrap_y;
+    self
+  }
+
   pub fn add(&mut self, item: T, x: f64, y: f64) {
+    let (x, y) = self.wrap(x, y);
+
     if x < self.xmin {
       panic!("x too small");
     } else if x > self.xmax {
@@ -51,163 +65,92 @@ impl<T: Clone> QuadTree<T> {
     }
 
     if self.leaf { // Is leaf, needs logic
-      match &self.data { // Empty, fill it and set position
-        None => {
-          self.x = x;
-          self.y = y;
-          self.data = Some(item);
-        },
-        Some(data) => { // Full, Split and cascade
-          let xmid = 0.5 * (self.xmax + self.xmin);
-          let ymid = 0.5 * (self.ymax + self.ymin);
-
-          // Make four children
-          self.q1 = Some(Box::new(QuadTree::new(
-            xmid,
-            self.xmax,
-            ymid,
-            self.ymax,
-          )));
-
-          self.q2 = Some(Box::new(QuadTree::new(
-            self.xmin,
-            xmid,
-            ymid,
-            self.ymax,
-          )));
-
-          self.q3 = Some(Box::new(QuadTree::new(
-            self.xmin,
-            xmid,
-            self.ymin,
-            ymid,
-          )));
-
-          self.q4 = Some(Box::new(QuadTree::new(
-            xmid,
-            self.xmax,
-            self.ymin,
-            ymid,
-          )));
-
-          // Put current data to children
-          if x > xmid && y > ymid {
-            if let Some(q1) = &mut self.q1 {
-              q1.data = Some(data.clone());
-            }
-          } else if x < xmid && y > ymid {
-            if let Some(q2) = &mut self.q2 {
-              q2.data = Some(data.clone());
-            }
-          } else if x < xmid && y < ymid {
-            if let Some(q3) = &mut self.q3 {
-              q3.data = Some(data.clone());
-            }
-          } else if x > xmid && y < ymid {
-            if let Some(q4) = &mut self.q4 {
-              q4.data = Some(data.clone());
-            }
-          }
-
-          if x > xmid && y > ymid {
-            if let Some(q1) = &mut self.q1 {
-              q1.add(item, x, y);
-            }
-          } else if x < xmid && y > ymid {
-            if let Some(q2) = &mut self.q2 {
-              q2.add(item, x, y);
-            }
-          } else if x < xmid && y < ymid {
-            if let Some(q3) = &mut self.q3 {
-              q3.add(item, x, y);
-            }
-          } else if x > xmid && y < ymid {
-            if let Some(q4) = &mut self.q4 {
-              q4.add(item, x, y);
-            }
-          }
-        }
+      if self.items.is_empty() || self.xmax - self.xmin < MIN_SPAN { // Room left, keep it here
+        self.items.push((item, x, y));
+        return;
       }
-    } else { // Not a leaf node, pass to children
+
+      // Full, Split and cascade
       let xmid = 0.5 * (self.xmax + self.xmin);
       let ymid = 0.5 * (self.ymax + self.ymin);
 
-      if x > xmid && y > ymid {
-        if let Some(q1) = &mut self.q1 {
-          q1.add(item, x, y);
-        }
-      } else if x < xmid && y > ymid {
-        if let Some(q2) = &mut self.q2 {
-          q2.add(item, x, y);
-        }
-      } else if x < xmid && y < ymid {
-        if let Some(q3) = &mut self.q3 {
-          q3.add(item, x, y);
-        }
-      } else if x > xmid && y < ymid {
-        if let Some(q4) = &mut self.q4 {
-          q4.add(item, x, y);
-        }
+      // Make four children
+      self.q1 = Some(Box::new(QuadTree::new(xmid, self.xmax, ymid, self.ymax)));
+      self.q2 = Some(Box::new(QuadTree::new(self.xmin, xmid, ymid, self.ymax)));
+      self.q3 = Some(Box::new(QuadTree::new(self.xmin, xmid, self.ymin, ymid)));
+      self.q4 = Some(Box::new(QuadTree::new(xmid, self.xmax, self.ymin, ymid)));
+      self.leaf = false;
+
+      // Put current data to children
+      for (data, data_x, data_y) in std::mem::take(&mut self.items) {
+        self.child(data_x, data_y).add(data, data_x, data_y);
       }
     }
-  }
 
-  pub fn get_within(&self, x: f64, y: f64, radius: f64) -> Vec<T> {
-    let mut within = vec![];
+    self.child(x, y).add(item, x, y);
+  }
 
-    if self.leaf {
-      if let Some(data) = &self.data {
-        if ((self.x - x).powi(2) + (self.y - y).powi(2)).sqrt() <= radius {
-          within.push(data.clone());
-        }
-      }
-      return within;
-    }
+  // Brings a point on a periodic axis back inside the bounds
+  fn wrap(&self, x: f64, y: f64) -> (f64, f64) {
+    let x = if self.wrap_x { self.xmin + (x - self.xmin).rem_euclid(self.xmax - self.xmin) } else { x };
+    let y = if self.wrap_y { self.ymin + (y - self.ymin).rem_euclid(self.ymax - self.ymin) } else { y };
+    (x, y)
+  }
 
+  // The child whose quarter holds (x, y). Points on the middle lines go to the upper and right quarters.
+  fn child(&mut self, x: f64, y: f64) -> &mut QuadTree<T> {
     let xmid = 0.5 * (self.xmax + self.xmin);
     let ymid = 0.5 * (self.ymax + self.ymin);
 
-    if test_circle_rect((x, y, radius), (xmid, self.xmax, ymid, self.ymax)) {
-      if let Some(q1) = &self.q1 {
-        let got = q1.get_within(x, y, radius);
-        for item in got {
-          within.push(item);
-        }
-      }
-    }
+    let child = if x >= xmid && y >= ymid {
+      &mut self.q1
+    } else if x < xmid && y >= ymid {
+      &mut self.q2
+    } else if x < xmid {
+      &mut self.q3
+    } else {
+      &mut self.q4
+    };
+    child.as_mut().expect("Split nodes have all four children")
+  }
 
-    if test_circle_rect((x, y, radius), (self.xmin, xmid, ymid, self.ymax)) {
-      if let Some(q2) = &self.q2 {
-        let got = q2.get_within(x, y, radius);
-        for item in got {
-          within.push(item);
-        }
+  // Items within radius of (x, y). On periodic axes the radius has to be under half of the
+  // period, or items can be found more than once.
+  pub fn get_within(&self, x: f64, y: f64, radius: f64) -> Vec<T> {
+    let mut within = vec![];
+    let (x, y) = self.wrap(x, y);
+
+    let shifts = |wrap: bool, span: f64| if wrap { vec![0.0, -span, span] } else { vec![0.0] };
+    for shift_x in shifts(self.wrap_x, self.xmax - self.xmin) {
+      for shift_y in shifts(self.wrap_y, self.ymax - self.ymin) {
+        self.collect_within(x + shift_x, y + shift_y, radius, &mut within);
       }
     }
 
-    if test_circle_rect((x, y, radius), (self.xmin, xmid, self.ymin, ymid)) {
-      if let Some(q3) = &self.q3 {
-        let got = q3.get_within(x, y, radius);
-        for item in got {
-          within.push(item);
-        }
-      }
+    within
+  }
+
+  fn collect_within(&self, x: f64, y: f64, radius: f64, within: &mut Vec<T>) {
+    if !test_circle_rect((x, y, radius), (self.xmin, self.xmax, self.ymin, self.ymax)) {
+      return;
     }
 
-    if test_circle_rect((x, y, radius), (xmid, self.xmax, self.ymin, ymid)) {
-      if let Some(q4) = &self.q4 {
-        let got = q4.get_within(x, y, radius);
-        for item in got {
-          within.push(item);
+    if self.leaf {
+      for (data, data_x, data_y) in &self.items {
+        if ((data_x - x).powi(2) + (data_y - y).powi(2)).sqrt() <= radius {
+          within.push(data.clone());
         }
       }
+      return;
     }
 
-    within
+    for child in [&self.q1, &self.q2, &self.q3, &self.q4].iter().copied().flatten() {
+      child.collect_within(x, y, radius, within);
+    }
   }
 }
 
-fn test_circle_rect((circle_x, circle_y, circle_rad): (f64, f64, f64), (rect_left, rect_right, rect_top, rect_bottom): (f64, f64, f64, f64)) -> bool {
+fn test_circle_rect((circle_x, circle_y, circle_rad): (f64, f64, f64), (rect_left, rect_right, rect_bottom, rect_top): (f64, f64, f64, f64)) -> bool {
   let test_x = if circle_x < rect_left { rect_left } else if circle_x > rect_right { rect_right } else { circle_x };
   let test_y = if circle_y < rect_bottom { rect_bottom } else if circle_y > rect_top { rect_top } else { circle_y };
 
@@ -1,7 +1,9 @@
 use serde::{Serialize, Deserialize};
+use crate::{cell, flow, forces, generation, indenter, nutrient, species};
 use super::RustfilmError;
 
 #[derive(Serialize,Deserialize,Debug)]
+#[serde(default)]
 pub struct Settings {
   pub spring_k: f64,
   pub spring_relax_close: f64,
@@ -11,13 +13,90 @@ pub struct Settings {
   pub sineamp: f64,
   pub sineomega: f64,
   pub extforce_x: f64,
+  pub extforce_y: f64,
   pub lj_epsilon: f64,
   pub lj_sigma: f64,
   pub restraint_k: f64,
   pub repl_dist: f64,
   pub repl_min: f64,
   pub repl_epsilon: f64,
-  pub nrows: usize
+  pub nrows: usize,
+  pub inertial: bool,
+  pub mass: f64,
+  pub viscous: f64,
+  pub temperature: f64,
+  pub seed: u64,
+  pub load_start: f64,
+  pub ramp_time: f64,
+  pub chirp_omega: f64,
+  pub multisine: Vec<(f64, f64, f64)>, // (amplitude, angular frequency, phase)
+  pub force_table: Vec<(f64, f64)>, // (time, force)
+  pub disp_rate: f64,
+  pub disp_amp: f64,
+  pub disp_table: Vec<(f64, f64)>, // (time, displacement)
+  pub bond_profile: generation::BondProfile,
+  pub profile_k: f64,
+  pub core_radius: f64,
+  pub bond_disorder: f64,
+  pub rupture_strain: f64,
+  pub rupture_force: f64,
+  pub capture_dist: f64,
+  pub bond_type: cell::BondType,
+  pub kv_viscosity: f64,
+  pub maxwell_viscosity: f64,
+  pub bend_k: f64,
+  pub wall: forces::Wall,
+  pub wall_potential: forces::WallPotential,
+  pub wall_xmin: f64,
+  pub wall_xmax: f64,
+  pub wall_ymin: f64,
+  pub wall_ymax: f64,
+  pub well_x: f64,
+  pub well_y: f64,
+  pub well_radius: f64,
+  pub wall_polygon: Vec<(f64, f64)>, // vertices in order around the polygon
+  pub periodic_x: bool,
+  pub periodic_y: bool,
+  pub lees_edwards: forces::LeesEdwards,
+  pub shear_rate: f64,
+  pub shear_strain: f64,
+  pub growth: cell::Growth,
+  pub growth_rate: f64,
+  pub division_radius: f64,
+  pub nutrient: f64, // nutrient for Monod growth, in starting areas of the colony it can grow
+  pub monod_k: f64,
+  pub start_area: f64, // area of the growing cells when generated
+  pub nutrient_supply: nutrient::Supply,
+  pub nutrient_res: usize, // squares per side of the nutrient field
+  pub nutrient_diffusion: f64,
+  pub nutrient_uptake: f64, // uptake per area of cell and concentration
+  pub nutrient_stiffness: f64, // fraction of bond stiffness lost without nutrient
+  pub species: Vec<species::Species>,
+  pub interactions: Vec<species::Interaction>, // between every pair of species, row by row
+  pub species_pattern: species::Pattern,
+  pub species_fraction: f64,
+  pub stripe_width: f64,
+  pub active_force: f64, // self-propulsion of every free cell along its orientation
+  pub rot_diffusion: f64, // rotational diffusion constant of the orientations
+  pub anchoring: cell::Anchoring,
+  pub anchor_k: f64,
+  pub anchor_fraction: f64, // of free cells tethered
+  pub substrate_y: f64,
+  pub detach_force: f64, // tethers pulling harder than this let go, never at 0
+  pub flow: flow::Flow,
+  pub flow_speed: f64,
+  pub flow_drag: f64, // Stokes drag per radius, 6 pi times the viscosity of the fluid
+  pub flow_table: flow::FlowTable,
+  pub indenter: indenter::Shape,
+  pub tip_radius: f64,
+  pub cap_height: f64, // of a cap tip, up from its lowest point
+  pub tip_x: f64,
+  pub tip_y: f64, // lowest point of the tip before it moves in, top of the film if 0 at generation
+  pub indent_k: f64, // contact stiffness between the tip and the cells
+  pub indent_path: indenter::Path,
+  pub indent_rate: f64,
+  pub indent_depth: f64,
+  pub indent_table: Vec<(f64, f64)> // (time, depth)
 }
 
 impl Settings {
@@ -31,13 +110,90 @@ impl Settings {
       sineamp: 1.0,
       sineomega: std::f64::consts::PI,
       extforce_x: 0.05,
+      extforce_y: 0.05,
       lj_epsilon: 0.05,
       lj_sigma: 0.065,
       restraint_k: 10.0,
       repl_dist: 0.012,
       repl_min: 0.01,
       repl_epsilon: 5.0,
-      nrows: 10
+      nrows: 10,
+      inertial: false,
+      mass: 1.0,
+      viscous: 0.0,
+      temperature: 0.0,
+      seed: 0,
+      load_start: 0.0,
+      ramp_time: 1.0,
+      chirp_omega: 4.0 * std::f64::consts::PI,
+      multisine: vec![],
+      force_table: vec![],
+      disp_rate: 0.005,
+      disp_amp: 0.01,
+      disp_table: vec![],
+      bond_profile: generation::BondProfile::Uniform,
+      profile_k: 1.0,
+      core_radius: 0.25,
+      bond_disorder: 0.0,
+      rupture_strain: 0.0,
+      rupture_force: 0.0,
+      capture_dist: 0.0,
+      bond_type: cell::BondType::Elastic,
+      kv_viscosity: 1.0,
+      maxwell_viscosity: 10.0,
+      bend_k: 0.0,
+      wall: forces::Wall::None,
+      wall_potential: forces::WallPotential::Harmonic,
+      wall_xmin: 0.0,
+      wall_xmax: 1.0,
+      wall_ymin: 0.0,
+      wall_ymax: 1.0,
+      well_x: 0.5,
+      well_y: 0.5,
+      well_radius: 0.75,
+      wall_polygon: vec![],
+      periodic_x: false,
+      periodic_y: false,
+      lees_edwards: forces::LeesEdwards::None,
+      shear_rate: 0.1,
+      shear_strain: 0.05,
+      growth: cell::Growth::None,
+      growth_rate: 0.1,
+      division_radius: 0.0,
+      nutrient: 1.0,
+      monod_k: 0.25,
+      start_area: 0.0,
+      nutrient_supply: nutrient::Supply::None,
+      nutrient_res: 32,
+      nutrient_diffusion: 0.01,
+      nutrient_uptake: 10.0,
+      nutrient_stiffness: 0.0,
+      species: vec![species::Species::default()],
+      interactions: vec![species::Interaction::default()],
+      species_pattern: species::Pattern::Uniform,
+      species_fraction: 0.5,
+      stripe_width: 0.25,
+      active_force: 0.0,
+      rot_diffusion: 0.1,
+      anchoring: cell::Anchoring::None,
+      anchor_k: 10.0,
+      anchor_fraction: 1.0,
+      substrate_y: 0.0,
+      detach_force: 0.0,
+      flow: flow::Flow::None,
+      flow_speed: 0.1,
+      flow_drag: 100.0,
+      flow_table: flow::FlowTable::default(),
+      indenter: indenter::Shape::None,
+      tip_radius: 0.1,
+      cap_height: 0.05,
+      tip_x: 0.5,
+      tip_y: 0.0,
+      indent_k: 100.0,
+      indent_path: indenter::Path::Ramp,
+      indent_rate: 0.1,
+      indent_depth: 0.1,
+      indent_table: vec![]
     }
   }
 
@@ -96,6 +252,13 @@ impl Settings {
       }
     }
 
+    if let Some(extforce_y) = matches.value_of("extforce_y") {
+      match extforce_y.parse::<f64>() {
+        Ok(extforce_y) => self.extforce_y = extforce_y,
+        Err(_e) => return Some(RustfilmError{error: "extforce_y failed to parse".to_string()})
+      }
+    }
+
     if let Some(lj_epsilon) = matches.value_of("lj_epsilon") {
       match lj_epsilon.parse::<f64>() {
         Ok(lj_epsilon) => self.lj_epsilon = lj_epsilon,
@@ -163,8 +326,729 @@ impl Settings {
       }
     }
 
+    if matches.is_present("inertial") {
+      self.inertial = true;
+    }
+
+    if let Some(mass) = matches.value_of("mass") {
+      match mass.parse::<f64>() {
+        Ok(mass) => self.mass = mass,
+        Err(_e) => return Some(RustfilmError{error: "mass failed to parse".to_string()})
+      }
+      if self.mass <= 0.0 {
+        return Some(RustfilmError{error: "mass must be positive".to_string()});
+      }
+    }
+
+    if let Some(viscous) = matches.value_of("viscous") {
+      match viscous.parse::<f64>() {
+        Ok(viscous) => self.viscous = viscous,
+        Err(_e) => return Some(RustfilmError{error: "viscous failed to parse".to_string()})
+      }
+      if self.viscous < 0.0 {
+        return Some(RustfilmError{error: "viscous must be nonnegative".to_string()});
+      }
+    }
+
+    if let Some(temperature) = matches.value_of("temperature") {
+      match temperature.parse::<f64>() {
+        Ok(temperature) => self.temperature = temperature,
+        Err(_e) => return Some(RustfilmError{error: "temperature failed to parse".to_string()})
+      }
+      if self.temperature < 0.0 {
+        return Some(RustfilmError{error: "temperature must be nonnegative".to_string()});
+      }
+    }
+
+    if let Some(seed) = matches.value_of("seed") {
+      match seed.parse::<u64>() {
+        Ok(seed) => self.seed = seed,
+        Err(_e) => return Some(RustfilmError{error: "seed failed to parse".to_string()})
+      }
+    }
+
+    if let Some(load_start) = matches.value_of("load_start") {
+      match load_start.parse::<f64>() {
+        Ok(load_start) => self.load_start = load_start,
+        Err(_e) => return Some(RustfilmError{error: "load_start failed to parse".to_string()})
+      }
+      if self.load_start < 0.0 {
+        return Some(RustfilmError{error: "load_start must be nonnegative".to_string()});
+      }
+    }
+
+    if let Some(ramp_time) = matches.value_of("ramp_time") {
+      match ramp_time.parse::<f64>() {
+        Ok(ramp_time) => self.ramp_time = ramp_time,
+        Err(_e) => return Some(RustfilmError{error: "ramp_time failed to parse".to_string()})
+      }
+      if self.ramp_time <= 0.0 {
+        return Some(RustfilmError{error: "ramp_time must be positive".to_string()});
+      }
+    }
+
+    if let Some(chirp_omega) = matches.value_of("chirp_omega") {
+      match chirp_omega.parse::<f64>() {
+        Ok(chirp_omega) => self.chirp_omega = chirp_omega,
+        Err(_e) => return Some(RustfilmError{error: "chirp_omega failed to parse".to_string()})
+      }
+      if self.chirp_omega <= 0.0 {
+        return Some(RustfilmError{error: "chirp_omega must be positive".to_string()});
+      }
+    }
+
+    // Comma separated amplitude:omega:phase triples
+    if let Some(multisine) = matches.value_of("multisine") {
+      self.multisine = vec![];
+      for component in multisine.split(',') {
+        let parts: Result<Vec<f64>, _> = component.split(':').map(|p| p.trim().parse::<f64>()).collect();
+        match parts {
+          Ok(parts) if parts.len() == 3 => self.multisine.push((parts[0], parts[1], parts[2])),
+          _ => return Some(RustfilmError{error: "multisine failed to parse".to_string()})
+        }
+      }
+    }
+
+    if let Some(force_table) = matches.value_of("force_table") {
+      match read_table(force_table, "force table") {
+        Ok(table) => self.force_table = table,
+        Err(error) => return Some(error)
+      }
+    }
+
+    if let Some(disp_rate) = matches.value_of("disp_rate") {
+      match disp_rate.parse::<f64>() {
+        Ok(disp_rate) => self.disp_rate = disp_rate,
+        Err(_e) => return Some(RustfilmError{error: "disp_rate failed to parse".to_string()})
+      }
+    }
+
+    if let Some(disp_amp) = matches.value_of("disp_amp") {
+      match disp_amp.parse::<f64>() {
+        Ok(disp_amp) => self.disp_amp = disp_amp,
+        Err(_e) => return Some(RustfilmError{error: "disp_amp failed to parse".to_string()})
+      }
+    }
+
+    if let Some(disp_table) = matches.value_of("disp_table") {
+      match read_table(disp_table, "displacement table") {
+        Ok(table) => self.disp_table = table,
+        Err(error) => return Some(error)
+      }
+    }
+
+    if let Some(bond_profile) = matches.value_of("bond_profile") {
+      match generation::profile_enum(&bond_profile.to_lowercase()[..]) {
+        Some(bond_profile) => self.bond_profile = bond_profile,
+        None => return Some(RustfilmError{error: "Unknown bond_profile".to_string()})
+      }
+    }
+
+    if let Some(profile_k) = matches.value_of("profile_k") {
+      match profile_k.parse::<f64>() {
+        Ok(profile_k) => self.profile_k = profile_k,
+        Err(_e) => return Some(RustfilmError{error: "profile_k failed to parse".to_string()})
+      }
+      if self.profile_k <= 0.0 {
+        return Some(RustfilmError{error: "profile_k must be positive".to_string()});
+      }
+    }
+
+    if let Some(core_radius) = matches.value_of("core_radius") {
+      match core_radius.parse::<f64>() {
+        Ok(core_radius) => self.core_radius = core_radius,
+        Err(_e) => return Some(RustfilmError{error: "core_radius failed to parse".to_string()})
+      }
+      if self.core_radius < 0.0 {
+        return Some(RustfilmError{error: "core_radius must be nonnegative".to_string()});
+      }
+    }
+
+    if let Some(bond_disorder) = matches.value_of("bond_disorder") {
+      match bond_disorder.parse::<f64>() {
+        Ok(bond_disorder) => self.bond_disorder = bond_disorder,
+        Err(_e) => return Some(RustfilmError{error: "bond_disorder failed to parse".to_string()})
+      }
+      if self.bond_disorder < 0.0 {
+        return Some(RustfilmError{error: "bond_disorder must be nonnegative".to_string()});
+      }
+    }
+
+    if let Some(rupture_strain) = matches.value_of("rupture_strain") {
+      match rupture_strain.parse::<f64>() {
+        Ok(rupture_strain) => self.rupture_strain = rupture_strain,
+        Err(_e) => return Some(RustfilmError{error: "rupture_strain failed to parse".to_string()})
+      }
+      if self.rupture_strain < 0.0 {
+        return Some(RustfilmError{error: "rupture_strain must be nonnegative".to_string()});
+      }
+    }
+
+    if let Some(rupture_force) = matches.value_of("rupture_force") {
+      match rupture_force.parse::<f64>() {
+        Ok(rupture_force) => self.rupture_force = rupture_force,
+        Err(_e) => return Some(RustfilmError{error: "rupture_force failed to parse".to_string()})
+      }
+      if self.rupture_force < 0.0 {
+        return Some(RustfilmError{error: "rupture_force must be nonnegative".to_string()});
+      }
+    }
+
+    if let Some(capture_dist) = matches.value_of("capture_dist") {
+      match capture_dist.parse::<f64>() {
+        Ok(capture_dist) => self.capture_dist = capture_dist,
+        Err(_e) => return Some(RustfilmError{error: "capture_dist failed to parse".to_string()})
+      }
+      if self.capture_dist < 0.0 {
+        return Some(RustfilmError{error: "capture_dist must be nonnegative".to_string()});
+      }
+    }
+    if let Some(error) = self.check_capture() {
+      return Some(error);
+    }
+
+    if let Some(bond_type) = matches.value_of("bond_type") {
+      match cell::bond_type_enum(&bond_type.to_lowercase()[..]) {
+        Some(bond_type) => self.bond_type = bond_type,
+        None => return Some(RustfilmError{error: "Unknown bond_type".to_string()})
+      }
+    }
+
+    if let Some(kv_viscosity) = matches.value_of("kv_viscosity") {
+      match kv_viscosity.parse::<f64>() {
+        Ok(kv_viscosity) => self.kv_viscosity = kv_viscosity,
+        Err(_e) => return Some(RustfilmError{error: "kv_viscosity failed to parse".to_string()})
+      }
+      if self.kv_viscosity <= 0.0 {
+        return Some(RustfilmError{error: "kv_viscosity must be positive".to_string()});
+      }
+    }
+
+    if let Some(maxwell_viscosity) = matches.value_of("maxwell_viscosity") {
+      match maxwell_viscosity.parse::<f64>() {
+        Ok(maxwell_viscosity) => self.maxwell_viscosity = maxwell_viscosity,
+        Err(_e) => return Some(RustfilmError{error: "maxwell_viscosity failed to parse".to_string()})
+      }
+      if self.maxwell_viscosity <= 0.0 {
+        return Some(RustfilmError{error: "maxwell_viscosity must be positive".to_string()});
+      }
+    }
+
+    if let Some(bend_k) = matches.value_of("bend_k") {
+      match bend_k.parse::<f64>() {
+        Ok(bend_k) => self.bend_k = bend_k,
+        Err(_e) => return Some(RustfilmError{error: "bend_k failed to parse".to_string()})
+      }
+      if self.bend_k < 0.0 {
+        return Some(RustfilmError{error: "bend_k must be nonnegative".to_string()});
+      }
+    }
+
+    if let Some(wall) = matches.value_of("wall") {
+      match forces::wall_enum(&wall.to_lowercase()[..]) {
+        Some(wall) => self.wall = wall,
+        None => return Some(RustfilmError{error: "Unknown wall".to_string()})
+      }
+    }
+
+    if let Some(wall_potential) = matches.value_of("wall_potential") {
+      match forces::potential_enum(&wall_potential.to_lowercase()[..]) {
+        Some(wall_potential) => self.wall_potential = wall_potential,
+        None => return Some(RustfilmError{error: "Unknown wall_potential".to_string()})
+      }
+    }
+
+    if let Some(wall_xmin) = matches.value_of("wall_xmin") {
+      match wall_xmin.parse::<f64>() {
+        Ok(wall_xmin) => self.wall_xmin = wall_xmin,
+        Err(_e) => return Some(RustfilmError{error: "wall_xmin failed to parse".to_string()})
+      }
+    }
+
+    if let Some(wall_xmax) = matches.value_of("wall_xmax") {
+      match wall_xmax.parse::<f64>() {
+        Ok(wall_xmax) => self.wall_xmax = wall_xmax,
+        Err(_e) => return Some(RustfilmError{error: "wall_xmax failed to parse".to_string()})
+      }
+    }
+    if self.wall_xmin >= self.wall_xmax {
+      return Some(RustfilmError{error: "wall_xmin must be less than wall_xmax".to_string()});
+    }
+
+    if let Some(wall_ymin) = matches.value_of("wall_ymin") {
+      match wall_ymin.parse::<f64>() {
+        Ok(wall_ymin) => self.wall_ymin = wall_ymin,
+        Err(_e) => return Some(RustfilmError{error: "wall_ymin failed to parse".to_string()})
+      }
+    }
+
+    if let Some(wall_ymax) = matches.value_of("wall_ymax") {
+      match wall_ymax.parse::<f64>() {
+        Ok(wall_ymax) => self.wall_ymax = wall_ymax,
+        Err(_e) => return Some(RustfilmError{error: "wall_ymax failed to parse".to_string()})
+      }
+    }
+    if self.wall_ymin >= self.wall_ymax {
+      return Some(RustfilmError{error: "wall_ymin must be less than wall_ymax".to_string()});
+    }
+
+    if let Some(well_x) = matches.value_of("well_x") {
+      match well_x.parse::<f64>() {
+        Ok(well_x) => self.well_x = well_x,
+        Err(_e) => return Some(RustfilmError{error: "well_x failed to parse".to_string()})
+      }
+    }
+
+    if let Some(well_y) = matches.value_of("well_y") {
+      match well_y.parse::<f64>() {
+        Ok(well_y) => self.well_y = well_y,
+        Err(_e) => return Some(RustfilmError{error: "well_y failed to parse".to_string()})
+      }
+    }
+
+    if let Some(well_radius) = matches.value_of("well_radius") {
+      match well_radius.parse::<f64>() {
+        Ok(well_radius) => self.well_radius = well_radius,
+        Err(_e) => return Some(RustfilmError{error: "well_radius failed to parse".to_string()})
+      }
+      if self.well_radius <= 0.0 {
+        return Some(RustfilmError{error: "well_radius must be positive".to_string()});
+      }
+    }
+
+    if let Some(wall_polygon) = matches.value_of("wall_polygon") {
+      match read_rows(wall_polygon, "wall polygon") {
+        Ok(vertices) if vertices.len() >= 3 => self.wall_polygon = vertices,
+        Ok(_) => return Some(RustfilmError{error: "wall polygon needs at least 3 vertices".to_string()}),
+        Err(error) => return Some(error)
+      }
+    }
+
+    if matches.is_present("periodic_x") {
+      self.periodic_x = true;
+    }
+
+    if matches.is_present("periodic_y") {
+      self.periodic_y = true;
+    }
+
+    if let Some(lees_edwards) = matches.value_of("lees_edwards") {
+      match forces::lees_edwards_enum(&lees_edwards.to_lowercase()[..]) {
+        Some(lees_edwards) => self.lees_edwards = lees_edwards,
+        None => return Some(RustfilmError{error: "Unknown lees_edwards".to_string()})
+      }
+      // The sheared images are those of a grid periodic in both directions
+      if self.lees_edwards != forces::LeesEdwards::None {
+        self.periodic_x = true;
+        self.periodic_y = true;
+      }
+    }
+
+    if let Some(shear_rate) = matches.value_of("shear_rate") {
+      match shear_rate.parse::<f64>() {
+        Ok(shear_rate) => self.shear_rate = shear_rate,
+        Err(_e) => return Some(RustfilmError{error: "shear_rate failed to parse".to_string()})
+      }
+    }
+
+    if let Some(shear_strain) = matches.value_of("shear_strain") {
+      match shear_strain.parse::<f64>() {
+        Ok(shear_strain) => self.shear_strain = shear_strain,
+        Err(_e) => return Some(RustfilmError{error: "shear_strain failed to parse".to_string()})
+      }
+    }
+
+    if let Some(growth) = matches.value_of("growth") {
+      match cell::growth_enum(&growth.to_lowercase()[..]) {
+        Some(growth) => self.growth = growth,
+        None => return Some(RustfilmError{error: "Unknown growth".to_string()})
+      }
+    }
+
+    if let Some(growth_rate) = matches.value_of("growth_rate") {
+      match growth_rate.parse::<f64>() {
+        Ok(growth_rate) => self.growth_rate = growth_rate,
+        Err(_e) => return Some(RustfilmError{error: "growth_rate failed to parse".to_string()})
+      }
+      if self.growth_rate < 0.0 {
+        return Some(RustfilmError{error: "growth_rate must be nonnegative".to_string()});
+      }
+    }
+
+    if let Some(division_radius) = matches.value_of("division_radius") {
+      match division_radius.parse::<f64>() {
+        Ok(division_radius) => self.division_radius = division_radius,
+        Err(_e) => return Some(RustfilmError{error: "division_radius failed to parse".to_string()})
+      }
+      if self.division_radius <= 0.0 {
+        return Some(RustfilmError{error: "division_radius must be positive".to_string()});
+      }
+    }
+
+    if let Some(nutrient) = matches.value_of("nutrient") {
+      match nutrient.parse::<f64>() {
+        Ok(nutrient) => self.nutrient = nutrient,
+        Err(_e) => return Some(RustfilmError{error: "nutrient failed to parse".to_string()})
+      }
+      if self.nutrient < 0.0 {
+        return Some(RustfilmError{error: "nutrient must be nonnegative".to_string()});
+      }
+    }
+
+    if let Some(monod_k) = matches.value_of("monod_k") {
+      match monod_k.parse::<f64>() {
+        Ok(monod_k) => self.monod_k = monod_k,
+        Err(_e) => return Some(RustfilmError{error: "monod_k failed to parse".to_string()})
+      }
+      if self.monod_k <= 0.0 {
+        return Some(RustfilmError{error: "monod_k must be positive".to_string()});
+      }
+    }
+
+    if let Some(nutrient_supply) = matches.value_of("nutrient_supply") {
+      match nutrient::supply_enum(&nutrient_supply.to_lowercase()[..]) {
+        Some(nutrient_supply) => self.nutrient_supply = nutrient_supply,
+        None => return Some(RustfilmError{error: "Unknown nutrient_supply".to_string()})
+      }
+      let closed = match self.nutrient_supply {
+        nutrient::Supply::None => false,
+        nutrient::Supply::Edges => self.periodic_x && self.periodic_y,
+        nutrient::Supply::Bottom | nutrient::Supply::Top => self.periodic_y,
+      };
+      if closed {
+        return Some(RustfilmError{error: "nutrient_supply needs an edge that isn't periodic".to_string()});
+      }
+    }
+
+    if let Some(nutrient_res) = matches.value_of("nutrient_res") {
+      match nutrient_res.parse::<usize>() {
+        Ok(nutrient_res) => self.nutrient_res = nutrient_res,
+        Err(_e) => return Some(RustfilmError{error: "nutrient_res failed to parse".to_string()})
+      }
+      if self.nutrient_res == 0 {
+        return Some(RustfilmError{error: "nutrient_res must be positive".to_string()});
+      }
+    }
+
+    if let Some(nutrient_diffusion) = matches.value_of("nutrient_diffusion") {
+      match nutrient_diffusion.parse::<f64>() {
+        Ok(nutrient_diffusion) => self.nutrient_diffusion = nutrient_diffusion,
+        Err(_e) => return Some(RustfilmError{error: "nutrient_diffusion failed to parse".to_string()})
+      }
+      if self.nutrient_diffusion <= 0.0 {
+        return Some(RustfilmError{error: "nutrient_diffusion must be positive".to_string()});
+      }
+    }
+
+    if let Some(nutrient_uptake) = matches.value_of("nutrient_uptake") {
+      match nutrient_uptake.parse::<f64>() {
+        Ok(nutrient_uptake) => self.nutrient_uptake = nutrient_uptake,
+        Err(_e) => return Some(RustfilmError{error: "nutrient_uptake failed to parse".to_string()})
+      }
+      if self.nutrient_uptake < 0.0 {
+        return Some(RustfilmError{error: "nutrient_uptake must be nonnegative".to_string()});
+      }
+    }
+
+    if let Some(nutrient_stiffness) = matches.value_of("nutrient_stiffness") {
+      match nutrient_stiffness.parse::<f64>() {
+        Ok(nutrient_stiffness) => self.nutrient_stiffness = nutrient_stiffness,
+        Err(_e) => return Some(RustfilmError{error: "nutrient_stiffness failed to parse".to_string()})
+      }
+      if !(0.0..=1.0).contains(&self.nutrient_stiffness) {
+        return Some(RustfilmError{error: "nutrient_stiffness must be between 0 and 1".to_string()});
+      }
+    }
+
+    if let Some(species_table) = matches.value_of("species") {
+      match species::read_table(species_table) {
+        Ok((species, interactions)) => {
+          self.species = species;
+          self.interactions = interactions;
+        },
+        Err(error) => return Some(error)
+      }
+    }
+
+    if let Some(species_pattern) = matches.value_of("species_pattern") {
+      match species::pattern_enum(&species_pattern.to_lowercase()[..]) {
+        Some(species_pattern) => self.species_pattern = species_pattern,
+        None => return Some(RustfilmError{error: "Unknown species_pattern".to_string()})
+      }
+    }
+
+    // Patterns need a second species, which is a copy of the first unless the table has one
+    if self.species_pattern != species::Pattern::Uniform && self.species.len() < 2 {
+      self.species.push(self.species[0]);
+      self.interactions = vec![self.interactions[0]; 4];
+    }
+
+    if let Some(species_fraction) = matches.value_of("species_fraction") {
+      match species_fraction.parse::<f64>() {
+        Ok(species_fraction) => self.species_fraction = species_fraction,
+        Err(_e) => return Some(RustfilmError{error: "species_fraction failed to parse".to_string()})
+      }
+      if !(0.0..=1.0).contains(&self.species_fraction) {
+        return Some(RustfilmError{error: "species_fraction must be between 0 and 1".to_string()});
+      }
+    }
+
+    if let Some(stripe_width) = matches.value_of("stripe_width") {
+      match stripe_width.parse::<f64>() {
+        Ok(stripe_width) => self.stripe_width = stripe_width,
+        Err(_e) => return Some(RustfilmError{error: "stripe_width failed to parse".to_string()})
+      }
+      if self.stripe_width <= 0.0 {
+        return Some(RustfilmError{error: "stripe_width must be positive".to_string()});
+      }
+    }
+
+    if let Some(active_force) = matches.value_of("active_force") {
+      match active_force.parse::<f64>() {
+        Ok(active_force) => self.active_force = active_force,
+        Err(_e) => return Some(RustfilmError{error: "active_force failed to parse".to_string()})
+      }
+      if self.active_force < 0.0 {
+        return Some(RustfilmError{error: "active_force must be nonnegative".to_string()});
+      }
+    }
+
+    if let Some(rot_diffusion) = matches.value_of("rot_diffusion") {
+      match rot_diffusion.parse::<f64>() {
+        Ok(rot_diffusion) => self.rot_diffusion = rot_diffusion,
+        Err(_e) => return Some(RustfilmError{error: "rot_diffusion failed to parse".to_string()})
+      }
+      if self.rot_diffusion < 0.0 {
+        return Some(RustfilmError{error: "rot_diffusion must be nonnegative".to_string()});
+      }
+    }
+
+    if let Some(anchoring) = matches.value_of("anchoring") {
+      match cell::anchoring_enum(&anchoring.to_lowercase()[..]) {
+        Some(anchoring) => self.anchoring = anchoring,
+        None => return Some(RustfilmError{error: "Unknown anchoring".to_string()})
+      }
+    }
+
+    if let Some(anchor_k) = matches.value_of("anchor_k") {
+      match anchor_k.parse::<f64>() {
+        Ok(anchor_k) => self.anchor_k = anchor_k,
+        Err(_e) => return Some(RustfilmError{error: "anchor_k failed to parse".to_string()})
+      }
+      if self.anchor_k < 0.0 {
+        return Some(RustfilmError{error: "anchor_k must be nonnegative".to_string()});
+      }
+    }
+
+    if let Some(anchor_fraction) = matches.value_of("anchor_fraction") {
+      match anchor_fraction.parse::<f64>() {
+        Ok(anchor_fraction) => self.anchor_fraction = anchor_fraction,
+        Err(_e) => return Some(RustfilmError{error: "anchor_fraction failed to parse".to_string()})
+      }
+      if !(0.0..=1.0).contains(&self.anchor_fraction) {
+        return Some(RustfilmError{error: "anchor_fraction must be between 0 and 1".to_string()});
+      }
+    }
+
+    if let Some(substrate_y) = matches.value_of("substrate_y") {
+      match substrate_y.parse::<f64>() {
+        Ok(substrate_y) => self.substrate_y = substrate_y,
+        Err(_e) => return Some(RustfilmError{error: "substrate_y failed to parse".to_string()})
+      }
+    }
+
+    if let Some(detach_force) = matches.value_of("detach_force") {
+      match detach_force.parse::<f64>() {
+        Ok(detach_force) => self.detach_force = detach_force,
+        Err(_e) => return Some(RustfilmError{error: "detach_force failed to parse".to_string()})
+      }
+      if self.detach_force < 0.0 {
+        return Some(RustfilmError{error: "detach_force must be nonnegative".to_string()});
+      }
+    }
+
+    if let Some(flow) = matches.value_of("flow") {
+      match flow::flow_enum(&flow.to_lowercase()[..]) {
+        Some(flow) => self.flow = flow,
+        None => return Some(RustfilmError{error: "Unknown flow".to_string()})
+      }
+    }
+
+    if let Some(flow_speed) = matches.value_of("flow_speed") {
+      match flow_speed.parse::<f64>() {
+        Ok(flow_speed) => self.flow_speed = flow_speed,
+        Err(_e) => return Some(RustfilmError{error: "flow_speed failed to parse".to_string()})
+      }
+    }
+
+    if let Some(flow_drag) = matches.value_of("flow_drag") {
+      match flow_drag.parse::<f64>() {
+        Ok(flow_drag) => self.flow_drag = flow_drag,
+        Err(_e) => return Some(RustfilmError{error: "flow_drag failed to parse".to_string()})
+      }
+      if self.flow_drag < 0.0 {
+        return Some(RustfilmError{error: "flow_drag must be nonnegative".to_string()});
+      }
+    }
+
+    if let Some(flow_table) = matches.value_of("flow_table") {
+      match flow::FlowTable::read(flow_table) {
+        Ok(table) => self.flow_table = table,
+        Err(error) => return Some(error)
+      }
+    }
+    if self.flow == flow::Flow::Table && self.flow_table.vel.is_empty() {
+      return Some(RustfilmError{error: "table flow needs a flow_table".to_string()});
+    }
+    if self.flow == flow::Flow::Poiseuille && self.wall_ymax <= self.wall_ymin {
+      return Some(RustfilmError{error: "poiseuille flow needs wall_ymax above wall_ymin".to_string()});
+    }
+
+    if let Some(indenter) = matches.value_of("indenter") {
+      match indenter::shape_enum(&indenter.to_lowercase()[..]) {
+        Some(indenter) => self.indenter = indenter,
+        None => return Some(RustfilmError{error: "Unknown indenter".to_string()})
+      }
+    }
+
+    if let Some(tip_radius) = matches.value_of("tip_radius") {
+      match tip_radius.parse::<f64>() {
+        Ok(tip_radius) => self.tip_radius = tip_radius,
+        Err(_e) => return Some(RustfilmError{error: "tip_radius failed to parse".to_string()})
+      }
+      if self.tip_radius <= 0.0 {
+        return Some(RustfilmError{error: "tip_radius must be positive".to_string()});
+      }
+    }
+
+    if let Some(cap_height) = matches.value_of("cap_height") {
+      match cap_height.parse::<f64>() {
+        Ok(cap_height) => self.cap_height = cap_height,
+        Err(_e) => return Some(RustfilmError{error: "cap_height failed to parse".to_string()})
+      }
+    }
+    if self.indenter == indenter::Shape::Cap && (self.cap_height <= 0.0 || self.cap_height > 2.0 * self.tip_radius) {
+      return Some(RustfilmError{error: "cap_height must be positive and at most twice tip_radius".to_string()});
+    }
+
+    if let Some(tip_x) = matches.value_of("tip_x") {
+      match tip_x.parse::<f64>() {
+        Ok(tip_x) => self.tip_x = tip_x,
+        Err(_e) => return Some(RustfilmError{error: "tip_x failed to parse".to_string()})
+      }
+    }
+
+    if let Some(tip_y) = matches.value_of("tip_y") {
+      match tip_y.parse::<f64>() {
+        Ok(tip_y) => self.tip_y = tip_y,
+        Err(_e) => return Some(RustfilmError{error: "tip_y failed to parse".to_string()})
+      }
+    }
+
+    if let Some(indent_k) = matches.value_of("indent_k") {
+      match indent_k.parse::<f64>() {
+        Ok(indent_k) => self.indent_k = indent_k,
+        Err(_e) => return Some(RustfilmError{error: "indent_k failed to parse".to_string()})
+      }
+      if self.indent_k < 0.0 {
+        return Some(RustfilmError{error: "indent_k must be nonnegative".to_string()});
+      }
+    }
+
+    if let Some(indent_path) = matches.value_of("indent_path") {
+      match indenter::path_enum(&indent_path.to_lowercase()[..]) {
+        Some(indent_path) => self.indent_path = indent_path,
+        None => return Some(RustfilmError{error: "Unknown indent_path".to_string()})
+      }
+    }
+
+    if let Some(indent_rate) = matches.value_of("indent_rate") {
+      match indent_rate.parse::<f64>() {
+        Ok(indent_rate) => self.indent_rate = indent_rate,
+        Err(_e) => return Some(RustfilmError{error: "indent_rate failed to parse".to_string()})
+      }
+      if self.indent_rate <= 0.0 {
+        return Some(RustfilmError{error: "indent_rate must be positive".to_string()});
+      }
+    }
+
+    if let Some(indent_depth) = matches.value_of("indent_depth") {
+      match indent_depth.parse::<f64>() {
+        Ok(indent_depth) => self.indent_depth = indent_depth,
+        Err(_e) => return Some(RustfilmError{error: "indent_depth failed to parse".to_string()})
+      }
+      if self.indent_depth <= 0.0 {
+        return Some(RustfilmError{error: "indent_depth must be positive".to_string()});
+      }
+    }
+
+    if let Some(indent_table) = matches.value_of("indent_table") {
+      match read_table(indent_table, "indent table") {
+        Ok(table) => self.indent_table = table,
+        Err(error) => return Some(error)
+      }
+    }
+    if self.indent_path == indenter::Path::Table && self.indent_table.is_empty() {
+      return Some(RustfilmError{error: "table indent_path needs an indent_table".to_string()});
+    }
+
     None
   }
+
+  // Cells are only captured closer than the shortest bond ruptures at, or a bond that just
+  // broke could be captured again straight away
+  pub fn check_capture(&self) -> Option<RustfilmError> {
+    if self.capture_dist <= 0.0 {
+      return None;
+    }
+    let relax = self.spring_relax_close.min(self.spring_relax_far);
+    let over_strain = self.rupture_strain > 0.0 && self.capture_dist >= relax * (1.0 + self.rupture_strain);
+    let over_force = self.rupture_force > 0.0 && self.capture_dist >= relax + self.rupture_force / self.spring_k;
+    if over_strain || over_force {
+      return Some(RustfilmError{error: "capture_dist must be less than the distance bonds rupture at".to_string()});
+    }
+    None
+  }
+}
+
+// Reads rows of "time value" whose times have to increase
+fn read_table(name: &str, what: &str) -> Result<Vec<(f64, f64)>, RustfilmError> {
+  let table = read_rows(name, what)?;
+  if table.windows(2).any(|rows| rows[1].0 <= rows[0].0) {
+    return Err(RustfilmError{error: format!("{} times must increase", what)});
+  }
+  Ok(table)
+}
+
+// Reads rows of two numbers, separated by whitespace or a comma. Blank lines and lines
+// starting with # are skipped.
+fn read_rows(name: &str, what: &str) -> Result<Vec<(f64, f64)>, RustfilmError> {
+  let contents = match std::fs::read_to_string(name) {
+    Ok(contents) => contents,
+    Err(_e) => return Err(RustfilmError{error: format!("Failed to read {}", what)})
+  };
+
+  let mut table: Vec<(f64, f64)> = vec![];
+  for line in contents.lines() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+    let row: Result<Vec<f64>, _> = line.split(|c: char| c == ',' || c.is_whitespace())
+      .filter(|p| !p.is_empty())
+      .map(|p| p.parse::<f64>())
+      .collect();
+    let row = match row {
+      Ok(row) if row.len() == 2 => (row[0], row[1]),
+      _ => return Err(RustfilmError{error: format!("{} failed to parse", what)})
+    };
+    table.push(row);
+  }
+
+  if table.is_empty() {
+    return Err(RustfilmError{error: format!("{} is empty", what)});
+  }
+  Ok(table)
 }
 
 impl Default for Settings {
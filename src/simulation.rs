@@ -1,51 +1,263 @@
-use crate::{forces, cell, settings, quadtree::QuadTree};
+use crate::{forces, cell, settings, noise, nutrient, species, flow, indenter, quadtree::QuadTree};
+use crate::integrator::{Derivs, Observer, Checkpoint, IntegratorError};
 use rayon::prelude::*;
 
+// Bounds of the QuadTree used for repulsion
+const TREE_MIN: f64 = -1.0;
+const TREE_MAX: f64 = 2.0;
+
+// Periodic axes wrap around the unit box. Positions are never wrapped themselves, so
+// displacements and strains stay continuous, only offsets between cells are.
+pub const PERIOD: f64 = 1.0;
+
+// Offset from one position to another at time t, to the nearest periodic image of the second.
+// Images across y are slid along x by the Lees-Edwards shear.
+pub fn separation(from: &cell::Pos, to: &cell::Pos, t: f64, settings: &settings::Settings) -> cell::Pos {
+  let mut offset = to.sub(from);
+  let images = images_y(from, to, settings);
+  if settings.periodic_y {
+    offset.y -= PERIOD * images;
+    offset.x -= PERIOD * images * forces::applied_shear(t, settings);
+  }
+  if settings.periodic_x {
+    offset.x -= PERIOD * (offset.x / PERIOD).round();
+  }
+  offset
+}
+
+// How many periods above from the nearest image of to in y is shifted down by
+fn images_y(from: &cell::Pos, to: &cell::Pos, settings: &settings::Settings) -> f64 {
+  if settings.periodic_y { ((to.y - from.y) / PERIOD).round() } else { 0.0 }
+}
+
+// Moves cells on periodic axes back into the box at time t, for drawing
+pub fn wrap_positions(grid: &mut [cell::Cell], t: f64, settings: &settings::Settings) {
+  for cell in grid.iter_mut() {
+    if settings.periodic_y {
+      let images = (cell.pos.y / PERIOD).floor();
+      cell.pos.y -= PERIOD * images;
+      cell.pos.x -= PERIOD * images * forces::applied_shear(t, settings);
+    }
+    if settings.periodic_x {
+      cell.pos.x = cell.pos.x.rem_euclid(PERIOD);
+    }
+  }
+}
+
 // Take in grid, return vector with x, y interlaced
-pub fn derivs(t: f64, y: &mut [cell::Cell], settings: &settings::Settings) -> Vec<f64> {
-  let mut tree = QuadTree::new(-1.0, 2.0, -1.0, 2.0);
-  for (i, y) in y.iter().enumerate() {
-    tree.add(i, y.pos.x, y.pos.y);
+pub fn derivs(t: f64, y: &mut [cell::Cell], settings: &settings::Settings) -> Result<Vec<f64>, IntegratorError> {
+  let mut force = net_force(t, y, settings)?;
+  if settings.lees_edwards != forces::LeesEdwards::None {
+    // The medium streams with the shear, dragging free cells along at their height
+    for (i, cell) in y.iter().enumerate() {
+      if !cell.fixed && cell.displace == forces::DispFunc::None {
+        force[i*2] += settings.damping * shear_flow(t, cell, settings);
+      }
+    }
+  }
+  if settings.flow != flow::Flow::None {
+    // The background flow drags free cells toward its velocity, which also resists their motion
+    for (i, cell) in y.iter().enumerate() {
+      if !cell.fixed && cell.displace == forces::DispFunc::None {
+        let drag = flow::drag(cell, settings);
+        let vel = flow::velocity(&cell.pos, settings);
+        force[i*2] += drag * vel.x;
+        force[i*2+1] += drag * vel.y;
+      }
+    }
   }
+  let mut derivs: Vec<f64> = force.iter().enumerate().map(|(k, f)| f / friction(&y[k / 2], settings)).collect();
+  for (i, cell) in y.iter().enumerate() {
+    if cell.displace != forces::DispFunc::None {
+      derivs[i*2] = forces::disp_velocity(&cell.displace, t, settings);
+    }
+  }
+  if has_dashpots(y) {
+    derivs = coupled_velocities(t, y, &force, &derivs, settings)?;
+  }
+  Ok(derivs)
+}
 
-  let lj_a = settings.repl_epsilon * num::pow(settings.repl_min, 12);
-  let lj_b = settings.repl_epsilon * num::pow(settings.repl_min, 6);
+// Drag on a cell per velocity, from the damping and the Stokes drag of the background flow
+fn friction(cell: &cell::Cell, settings: &settings::Settings) -> f64 {
+  settings.damping + flow::drag(cell, settings)
+}
 
-  let mut grid = vec![cell::Cell::new(0.0, 0.0, 0.0); y.len()];
-  grid.clone_from_slice(y);
+// Velocity along x of the medium at a cell under Lees-Edwards shear
+fn shear_flow(t: f64, cell: &cell::Cell, settings: &settings::Settings) -> f64 {
+  forces::applied_shear_rate(t, settings) * cell.pos.y
+}
 
-  let forces: Vec<(f64, f64)> = y.iter_mut().enumerate().map(|(i, mut cell_a)| {
-    let mut net_force = cell_a.neighbor_close.iter().map(|ind| {
-      let mut net_force = (0.0, 0.0);
-      let a_to_b = grid[*ind].pos.sub(&cell_a.pos);
-      let dist = a_to_b.norm();
-      let mut force = settings.spring_k * (dist - settings.spring_relax_close);
-      if force.abs() < 1e-7 {
-        force = 0.0;
+// Dashpots stop more than this fraction of the force being left over in the velocities
+const DASHPOT_TOL: f64 = 1e-12;
+const DASHPOT_ITER: usize = 200;
+
+fn has_dashpots(y: &[cell::Cell]) -> bool {
+  y.iter().any(|c| c.links.iter().any(|link| link.kind == cell::BondType::KelvinVoigt))
+}
+
+// Velocities of cells coupled by Kelvin-Voigt dashpots, solving friction * v = force + dashpot
+// forces. Fixed and displacement driven cells keep their velocities from held.
+fn coupled_velocities(
+  t: f64,
+  y: &[cell::Cell],
+  force: &[f64],
+  held: &[f64],
+  settings: &settings::Settings
+) -> Result<Vec<f64>, IntegratorError> {
+  let is_held = |i: usize| y[i].fixed || y[i].displace != forces::DispFunc::None;
+  // Dashpots to sliding Lees-Edwards images pull even on cells at rest, so that part goes with the force
+  let at_rest = if settings.lees_edwards != forces::LeesEdwards::None {
+    dashpot_forces(t, y, &vec![0.0; force.len()], settings)
+  } else {
+    vec![0.0; force.len()]
+  };
+  let b: Vec<f64> = (0..force.len()).map(|k| {
+    if is_held(k / 2) { settings.damping * held[k] } else { force[k] + at_rest[k] }
+  }).collect();
+
+  let drag = |v: &[f64]| {
+    let dashpot = dashpot_forces(t, y, v, settings);
+    Ok((0..v.len()).map(|k| {
+      if is_held(k / 2) { settings.damping * v[k] } else { friction(&y[k / 2], settings) * v[k] - (dashpot[k] - at_rest[k]) }
+    }).collect())
+  };
+  bicgstab(drag, &b, DASHPOT_TOL, DASHPOT_ITER)
+}
+
+// Force of the Kelvin-Voigt dashpots on each cell when cells move with vel, x and y interlaced
+fn dashpot_forces(t: f64, y: &[cell::Cell], vel: &[f64], settings: &settings::Settings) -> Vec<f64> {
+  let mut forces = vec![0.0; y.len() * 2];
+  for (i, cell_a) in y.iter().enumerate() {
+    for link in cell_a.links.iter().filter(|link| link.kind == cell::BondType::KelvinVoigt) {
+      if let Some((unit, rate)) = extension_rate(t, y, vel, i, link.parties.1, settings) {
+        forces[i*2] += settings.kv_viscosity * rate * unit.x;
+        forces[i*2+1] += settings.kv_viscosity * rate * unit.y;
       }
+    }
+  }
+  forces
+}
 
-      let unit_dist = cell::Pos{
-        x: a_to_b.x / dist,
-        y: a_to_b.y / dist
-      };
-      let check_nan = unit_dist.x.is_nan() || unit_dist.y.is_nan();
-      let check_inf = unit_dist.x.is_infinite() || unit_dist.y.is_infinite();
-      let check_nan = check_nan || force.is_nan();
-      let check_inf = check_inf || force.is_infinite();
-      if !check_nan && !check_inf {
-        net_force.0 += force * unit_dist.x;
-        net_force.1 += force * unit_dist.y;
+// Direction from cell i to cell j and the rate the distance between them grows at. Lees-Edwards
+// images of cell j move at the shear velocity of the box relative to it.
+fn extension_rate(t: f64, y: &[cell::Cell], vel: &[f64], i: usize, j: usize, settings: &settings::Settings) -> Option<(cell::Pos, f64)> {
+  let a_to_b = separation(&y[i].pos, &y[j].pos, t, settings);
+  let dist = a_to_b.norm();
+  if dist == 0.0 {
+    return None;
+  }
+  let unit = cell::Pos{x: a_to_b.x / dist, y: a_to_b.y / dist};
+  let image_vel = PERIOD * images_y(&y[i].pos, &y[j].pos, settings) * forces::applied_shear_rate(t, settings);
+  Some((unit, unit.x * (vel[j*2] - image_vel - vel[i*2]) + unit.y * (vel[j*2+1] - vel[i*2+1])))
+}
+
+// Velocities of every cell, x and y interlaced. Overdamped grids move with their derivatives.
+pub fn velocities(t: f64, y: &[cell::Cell], settings: &settings::Settings) -> Result<Vec<f64>, IntegratorError> {
+  if settings.inertial {
+    Ok(cell_velocities(y))
+  } else {
+    derivs(t, &mut y.to_vec(), settings)
+  }
+}
+
+// Moves displacement driven cells to where their path puts them at time t
+fn prescribe(t: f64, y: &mut [cell::Cell], settings: &settings::Settings) {
+  for cell in y.iter_mut() {
+    if cell.displace != forces::DispFunc::None {
+      let disp_func = forces::disp_func(&cell.displace);
+      cell.pos.x = cell.initial_pos.x + disp_func(t, settings);
+      cell.pos.y = cell.initial_pos.y;
+    }
+  }
+}
+
+// Take in grid, return vector with net force x, y interlaced. Fixed and displacement driven
+// cells get no force. Fails on cells with non-finite positions or forces, or outside of the QuadTree
+pub fn net_force(t: f64, y: &mut [cell::Cell], settings: &settings::Settings) -> Result<Vec<f64>, IntegratorError> {
+  prescribe(t, y, settings);
+  let internal = internal_forces(t, y, settings)?;
+  let tip = indenter::tip(t, settings);
+
+  let forces: Vec<(f64, f64)> = y.iter_mut().zip(internal).enumerate().map(|(i, (mut cell_a, mut net_force))| {
+    if !cell_a.fixed && cell_a.displace == forces::DispFunc::None {
+      if cell_a.force != forces::ForceFunc::None {
+        let force_func = forces::force_func(&cell_a.force);
+        let force = force_func(t, &mut cell_a, i, settings);
+        net_force.0 += force.x;
+        net_force.1 += force.y;
       }
-      net_force
-    }).fold((0.0, 0.0), |acc, (force_x, force_y)| {
-      (acc.0 + force_x, acc.1 + force_y)
-    });
+      if settings.active_force > 0.0 {
+        let active = forces::active_force(cell_a, settings);
+        net_force.0 += active.x;
+        net_force.1 += active.y;
+      }
+      if cell_a.tether.is_some() {
+        let tether = forces::tether_force(cell_a, settings);
+        net_force.0 += tether.x;
+        net_force.1 += tether.y;
+      }
+      if let Some(tip) = &tip {
+        let contact = tip.force(cell_a, settings);
+        net_force.0 += contact.x;
+        net_force.1 += contact.y;
+      }
+      if settings.wall != forces::Wall::None || cell_a.force != forces::ForceFunc::None {
+        let wall = forces::wall_force(cell_a, settings);
+        net_force.0 += wall.x;
+        net_force.1 += wall.y;
+      }
+    } else {
+      net_force = (0.0, 0.0);
+    }
+
+    if !net_force.0.is_finite() || !net_force.1.is_finite() {
+      return Err(IntegratorError::NonFinite{time: t, cell: i});
+    }
+    Ok(net_force)
+  }).collect::<Result<_, _>>()?;
+
+  let mut net_force: Vec<f64> = vec![];
+  for (x, y) in &forces {
+    net_force.push(*x); net_force.push(*y);
+  }
+  Ok(net_force)
+}
+
+// QuadTree of the cells to find the ones near each other with. Fails on cells with non-finite
+// positions or outside of the tree.
+fn cell_tree(t: f64, y: &[cell::Cell], settings: &settings::Settings) -> Result<QuadTree<usize>, IntegratorError> {
+  let (xmin, xmax) = if settings.periodic_x { (0.0, PERIOD) } else { (TREE_MIN, TREE_MAX) };
+  let (ymin, ymax) = if settings.periodic_y { (0.0, PERIOD) } else { (TREE_MIN, TREE_MAX) };
+  let mut tree = QuadTree::new(xmin, xmax, ymin, ymax).wrapping(settings.periodic_x, settings.periodic_y);
+  for (i, y) in y.iter().enumerate() {
+    if !y.pos.x.is_finite() || !y.pos.y.is_finite() {
+      return Err(IntegratorError::NonFinite{time: t, cell: i});
+    }
+    let outside_x = !settings.periodic_x && (y.pos.x < TREE_MIN || y.pos.x > TREE_MAX);
+    let outside_y = !settings.periodic_y && (y.pos.y < TREE_MIN || y.pos.y > TREE_MAX);
+    if outside_x || outside_y {
+      return Err(IntegratorError::OutOfBounds{time: t, cell: i, pos: y.pos});
+    }
+    tree.add(i, y.pos.x, y.pos.y);
+  }
+  Ok(tree)
+}
+
+// Spring and repulsion forces on each cell from the rest of the grid
+fn internal_forces(t: f64, y: &[cell::Cell], settings: &settings::Settings) -> Result<Vec<(f64, f64)>, IntegratorError> {
+  let tree = cell_tree(t, y, settings)?;
+  let lj_a = settings.repl_epsilon * num::pow(settings.repl_min, 12);
+  let lj_b = settings.repl_epsilon * num::pow(settings.repl_min, 6);
+
+  let grid = y;
 
-    let far_force = cell_a.neighbor_far.iter().map(|ind| {
+  y.iter().enumerate().map(|(i, cell_a)| {
+    let mut net_force = cell_a.links.iter().map(|link| {
       let mut net_force = (0.0, 0.0);
-      let a_to_b = grid[*ind].pos.sub(&cell_a.pos);
+      let a_to_b = separation(&cell_a.pos, &grid[link.parties.1].pos, t, settings);
       let dist = a_to_b.norm();
-      let mut force = settings.spring_k * (dist - settings.spring_relax_far);
+      let mut force = stiffness(link, cell_a.nutrient, grid[link.parties.1].nutrient, settings) * (dist - link.relax_length);
       if force.abs() < 1e-7 {
         force = 0.0;
       }
@@ -54,11 +266,7 @@ pub fn derivs(t: f64, y: &mut [cell::Cell], settings: &settings::Settings) -> Ve
         x: a_to_b.x / dist,
         y: a_to_b.y / dist
       };
-      let check_nan = unit_dist.x.is_nan() || unit_dist.y.is_nan();
-      let check_inf = unit_dist.x.is_infinite() || unit_dist.y.is_infinite();
-      let check_nan = check_nan || force.is_nan();
-      let check_inf = check_inf || force.is_infinite();
-      if !check_nan && !check_inf {
+      if dist > 0.0 {
         net_force.0 += force * unit_dist.x;
         net_force.1 += force * unit_dist.y;
       }
@@ -67,16 +275,14 @@ pub fn derivs(t: f64, y: &mut [cell::Cell], settings: &settings::Settings) -> Ve
       (acc.0 + force_x, acc.1 + force_y)
     });
 
-    net_force.0 += far_force.0;
-    net_force.1 += far_force.1;
-
     let close = tree.get_within(cell_a.pos.x, cell_a.pos.y, settings.repl_dist);
     let repl_force = close.iter().map(|ind| {
       let mut net_force = (0.0, 0.0);
-      let a_to_b = grid[*ind].pos.sub(&cell_a.pos);
+      let a_to_b = separation(&cell_a.pos, &grid[*ind].pos, t, settings);
       let dist = a_to_b.norm();
 
       let mut force = 12.0 * lj_a * dist.powi(-13) - lj_b * dist.powi(-7);
+      force *= species::interaction(settings, cell_a.species, grid[*ind].species).repl_epsilon;
       if force < 1e-7 {
         force = 0.0;
       }
@@ -85,11 +291,8 @@ pub fn derivs(t: f64, y: &mut [cell::Cell], settings: &settings::Settings) -> Ve
         x: a_to_b.x / dist,
         y: a_to_b.y / dist
       };
-      let check_nan = unit_dist.x.is_nan() || unit_dist.y.is_nan();
-      let check_inf = unit_dist.x.is_infinite() || unit_dist.y.is_infinite();
-      let check_nan = check_nan || force.is_nan();
-      let check_inf = check_inf || force.is_infinite();
-      if !check_nan && !check_inf {
+      // The tree also returns cell_a itself, which has no direction to push in
+      if dist > 0.0 {
         net_force.0 += force * unit_dist.x;
         net_force.1 += force * unit_dist.y;
       }
@@ -102,46 +305,354 @@ pub fn derivs(t: f64, y: &mut [cell::Cell], settings: &settings::Settings) -> Ve
     net_force.0 += repl_force.0;
     net_force.1 += repl_force.1;
 
-    if !cell_a.fixed {
-      if cell_a.force != forces::ForceFunc::None {
-        let force_func = forces::force_func(&cell_a.force);
-        let force = force_func(t, &mut cell_a, i, settings);
-        net_force.0 += force.x;
-        net_force.1 += force.y;
+    if !net_force.0.is_finite() || !net_force.1.is_finite() {
+      return Err(IntegratorError::NonFinite{time: t, cell: i});
+    }
+    Ok(net_force)
+  }).collect::<Result<Vec<_>, _>>().map(|mut forces| {
+    // Bending springs push on both arms and the vertex, so they are added after
+    for (i, cell_a) in y.iter().enumerate() {
+      for angle in &cell_a.angles {
+        let (_, first, second) = angle.parties;
+        let arms = (separation(&cell_a.pos, &y[first].pos, t, settings), separation(&cell_a.pos, &y[second].pos, t, settings));
+        if let Some((force_first, force_second)) = bend_forces(angle, arms.0, arms.1) {
+          forces[first].0 += force_first.x;
+          forces[first].1 += force_first.y;
+          forces[second].0 += force_second.x;
+          forces[second].1 += force_second.y;
+          forces[i].0 -= force_first.x + force_second.x;
+          forces[i].1 -= force_first.y + force_second.y;
+        }
       }
-    } else {
-      net_force = (0.0, 0.0);
     }
+    forces
+  })
+}
 
-    net_force.0 /= settings.damping;
-    net_force.1 /= settings.damping;
+// Spring constant of a bond, softened by nutrient_stiffness where its cells are starved
+fn stiffness(link: &cell::ForceLink, nutrient_a: f64, nutrient_b: f64, settings: &settings::Settings) -> f64 {
+  if settings.nutrient_stiffness == 0.0 {
+    return link.value;
+  }
+  link.value * (1.0 - settings.nutrient_stiffness * (1.0 - 0.5 * (nutrient_a + nutrient_b)))
+}
 
-    net_force
+// Forces of a bending spring on its first and second arm, given the offsets of the arms from
+// the vertex. The vertex takes the opposite of their sum.
+fn bend_forces(angle: &cell::AngleLink, first: cell::Pos, second: cell::Pos) -> Option<(cell::Pos, cell::Pos)> {
+  let first_sq = first.x * first.x + first.y * first.y;
+  let second_sq = second.x * second.x + second.y * second.y;
+  if first_sq == 0.0 || second_sq == 0.0 {
+    return None;
+  }
+
+  let theta = (first.x * second.y - first.y * second.x).atan2(first.x * second.x + first.y * second.y);
+  let mut delta = theta - angle.rest_angle;
+  delta -= 2.0 * std::f64::consts::PI * (delta / (2.0 * std::f64::consts::PI)).round();
+  let torque = -angle.value * delta;
+
+  Some((
+    cell::Pos{x: torque * first.y / first_sq, y: -torque * first.x / first_sq},
+    cell::Pos{x: -torque * second.y / second_sq, y: torque * second.x / second_sq}
+  ))
+}
+
+// Changes made to the grid between accepted steps. Returns whether the forces changed,
+// in which case integrators can't reuse derivatives from earlier states. Dividing cells
+// add to the end of the grid. The nutrient field goes last, so it steps with the grid as
+// it is kept.
+pub fn after_step(
+  t: f64,
+  dt: f64,
+  y: &mut Vec<cell::Cell>,
+  field: &mut Option<nutrient::Field>,
+  settings: &settings::Settings
+) -> Result<bool, IntegratorError> {
+  let relaxed = relax_maxwell(t, y, dt, settings);
+  let rebonded = update_bonds(t, y, settings)?;
+  let grown = grow(y, dt, settings);
+  let divided = divide(t, y, settings);
+  let turned = rotate(t, dt, y, settings);
+  let detached = detach(t, y, settings);
+  let fed = nutrient::feed(y, field, dt, settings);
+  Ok(relaxed || rebonded || grown || divided || turned || detached || fed)
+}
+
+// Grows the area of every growing cell over dt, exactly for a constant rate. Bonds lengthen
+// with the radii of both of their cells and the mass follows the area. Monod growth follows
+// the nutrient field where there is one and the nutrient left otherwise.
+fn grow(y: &mut [cell::Cell], dt: f64, settings: &settings::Settings) -> bool {
+  let monod = |nutrient: f64| settings.growth_rate * nutrient / (settings.monod_k + nutrient);
+  let mut rates: Vec<f64> = match settings.growth {
+    cell::Growth::None => return false,
+    cell::Growth::Exponential => vec![settings.growth_rate; y.len()],
+    cell::Growth::Monod if settings.nutrient_supply != nutrient::Supply::None => {
+      y.iter().map(|c| monod(c.nutrient)).collect()
+    },
+    cell::Growth::Monod => vec![monod(nutrient_left(y, settings)); y.len()],
+  };
+  for (rate, cell) in rates.iter_mut().zip(y.iter()) {
+    *rate *= species::of(settings, cell).growth;
+  }
+
+  let scales: Vec<f64> = rates.iter().map(|rate| (0.5 * rate * dt).exp()).collect();
+  let grown: Vec<f64> = y.iter().zip(&scales).map(|(c, scale)| {
+    if c.grows() { c.radius * (scale - 1.0) } else { 0.0 }
   }).collect();
+  for (i, cell) in y.iter_mut().enumerate() {
+    if grown[i] > 0.0 {
+      cell.radius += grown[i];
+      cell.mass *= scales[i] * scales[i];
+    }
+    for link in cell.links.iter_mut() {
+      link.relax_length += grown[i] + grown[link.parties.1];
+    }
+  }
+  true
+}
 
-  let mut derivs: Vec<f64> = vec![];
-  for (x, y) in &forces {
-    derivs.push(*x); derivs.push(*y);
+// Lets go of tethers pulling harder than detach_force
+fn detach(t: f64, y: &mut [cell::Cell], settings: &settings::Settings) -> bool {
+  if settings.detach_force <= 0.0 {
+    return false;
+  }
+  let mut detached = false;
+  for cell in y.iter_mut().filter(|c| c.tether.is_some()) {
+    if forces::tether_force(cell, settings).norm() > settings.detach_force {
+      cell.tether = None;
+      cell.detached = Some(t);
+      detached = true;
+    }
   }
-  derivs
+  detached
+}
+
+// Turns the orientation of every active cell by rotational diffusion over dt. The noise is
+// keyed on the time, so resumed runs turn the same way.
+fn rotate(t: f64, dt: f64, y: &mut [cell::Cell], settings: &settings::Settings) -> bool {
+  if settings.active_force <= 0.0 || settings.rot_diffusion == 0.0 {
+    return false;
+  }
+  let mut rng = noise::Rng::new(settings.seed ^ t.to_bits());
+  let spread = (2.0 * settings.rot_diffusion * dt).sqrt();
+  for cell in y.iter_mut() {
+    cell.orientation = (cell.orientation + spread * rng.gaussian()).rem_euclid(2.0 * std::f64::consts::PI);
+  }
+  true
+}
+
+// Nutrient left for Monod growth, used up by the area grown since the grid was generated
+pub fn nutrient_left(y: &[cell::Cell], settings: &settings::Settings) -> f64 {
+  if settings.start_area <= 0.0 {
+    return settings.nutrient;
+  }
+  let area: f64 = y.iter().filter(|c| c.grows()).map(|c| std::f64::consts::PI * c.radius * c.radius).sum();
+  (settings.nutrient - (area - settings.start_area) / settings.start_area).max(0.0)
+}
+
+// Random division axes tried per division
+const DIVISION_TRIES: usize = 8;
+
+// Splits every growing cell that has reached division_radius, scaled for its species, into two
+// touching daughters of
+// half its area, along whichever of the axes tried leaves them farthest from other cells.
+// The new daughter goes on the end of the grid and takes the bonds on its side, the bonds keep
+// how far they are stretched, and the two are bonded to each other at rest. Bending springs
+// around the mother are dropped.
+fn divide(t: f64, y: &mut Vec<cell::Cell>, settings: &settings::Settings) -> bool {
+  if settings.growth == cell::Growth::None {
+    return false;
+  }
+
+  let mothers: Vec<usize> = (0..y.len()).filter(|i| {
+    y[*i].grows() && y[*i].radius >= settings.division_radius * species::of(settings, &y[*i]).radius
+  }).collect();
+  for m in &mothers {
+    let m = *m;
+    let n = y.len();
+    // Keyed on the daughter's index, so resumed runs split the same way
+    let mut rng = noise::Rng::new(settings.seed.wrapping_add(n as u64));
+    let radius = y[m].radius / std::f64::consts::SQRT_2;
+    let center = y[m].pos;
+    let clearance = |half: &cell::Pos| {
+      y.iter().enumerate().filter(|(i, _)| *i != m).map(|(_, other)| {
+        let offset = separation(&center, &other.pos, t, settings);
+        let ahead = cell::Pos{x: offset.x - half.x, y: offset.y - half.y}.norm();
+        let behind = cell::Pos{x: offset.x + half.x, y: offset.y + half.y}.norm();
+        ahead.min(behind)
+      }).fold(f64::INFINITY, f64::min)
+    };
+    let half = (0..DIVISION_TRIES).map(|_| {
+      let angle = 2.0 * std::f64::consts::PI * rng.uniform();
+      let half = cell::Pos{x: radius * angle.cos(), y: radius * angle.sin()};
+      (clearance(&half), half)
+    }).fold((f64::NEG_INFINITY, cell::Pos::default()), |best, next| if next.0 > best.0 { next } else { best }).1;
+
+    let mut daughter = y[m].clone();
+    daughter.pos = cell::Pos{x: center.x + half.x, y: center.y + half.y};
+    daughter.initial_pos = cell::Pos{x: y[m].initial_pos.x + half.x, y: y[m].initial_pos.y + half.y};
+    daughter.radius = radius;
+    daughter.mass = 0.5 * y[m].mass;
+    daughter.links = vec![];
+    daughter.angles = vec![];
+    daughter.neighbor_close = vec![];
+    daughter.neighbor_far = vec![];
+    daughter.broken = 0;
+    daughter.parent = Some(m);
+    // The daughter has yet to attach to the substrate
+    daughter.tether = None;
+    daughter.detached = None;
+
+    let mother = &mut y[m];
+    mother.pos = cell::Pos{x: center.x - half.x, y: center.y - half.y};
+    mother.initial_pos = cell::Pos{x: mother.initial_pos.x - half.x, y: mother.initial_pos.y - half.y};
+    mother.radius = radius;
+    mother.mass *= 0.5;
+    mother.angles.clear();
+
+    for mut link in std::mem::take(&mut y[m].links) {
+      let j = link.parties.1;
+      let before = separation(&center, &y[j].pos, t, settings);
+      let to_daughter = before.x * half.x + before.y * half.y > 0.0;
+      let (owner, pos) = if to_daughter { (n, daughter.pos) } else { (m, y[m].pos) };
+      link.relax_length += separation(&pos, &y[j].pos, t, settings).norm() - before.norm();
+      link.parties.0 = owner;
+
+      for back in y[j].links.iter_mut().filter(|back| back.parties.1 == m) {
+        back.parties.1 = owner;
+        back.relax_length = link.relax_length;
+      }
+      if to_daughter {
+        let other = &mut y[j];
+        for k in other.neighbor_close.iter_mut().chain(other.neighbor_far.iter_mut()).filter(|k| **k == m) {
+          *k = n;
+        }
+        if y[m].neighbor_close.contains(&j) {
+          y[m].neighbor_close.retain(|k| *k != j);
+          daughter.neighbor_close.push(j);
+        }
+        if y[m].neighbor_far.contains(&j) {
+          y[m].neighbor_far.retain(|k| *k != j);
+          daughter.neighbor_far.push(j);
+        }
+        daughter.links.push(link);
+      } else {
+        y[m].links.push(link);
+      }
+    }
+
+    let sisters = 2.0 * radius;
+    let k = settings.spring_k * species::interaction(settings, daughter.species, daughter.species).spring_k;
+    y[m].links.push(cell::ForceLink::new(m, n, k, sisters, settings.bond_type));
+    daughter.links.push(cell::ForceLink::new(n, m, k, sisters, settings.bond_type));
+    y[m].neighbor_close.push(n);
+    daughter.neighbor_close.push(m);
+    for cell in y.iter_mut() {
+      cell.angles.retain(|angle| angle.parties.1 != m && angle.parties.2 != m);
+    }
+    y.push(daughter);
+  }
+
+  !mothers.is_empty()
+}
+
+// Lets the rest lengths of Maxwell bonds flow toward their lengths over dt, exact for a
+// bond held at its current length
+fn relax_maxwell(t: f64, y: &mut [cell::Cell], dt: f64, settings: &settings::Settings) -> bool {
+  let pos: Vec<cell::Pos> = y.iter().map(|c| c.pos).collect();
+  let nutrient: Vec<f64> = y.iter().map(|c| c.nutrient).collect();
+  let mut relaxed = false;
+  for (i, cell) in y.iter_mut().enumerate() {
+    for link in cell.links.iter_mut().filter(|link| link.kind == cell::BondType::Maxwell) {
+      let dist = separation(&pos[i], &pos[link.parties.1], t, settings).norm();
+      let k = stiffness(link, nutrient[i], nutrient[link.parties.1], settings);
+      let decay = (-k * dt / settings.maxwell_viscosity).exp();
+      link.relax_length = dist + (link.relax_length - dist) * decay;
+      relaxed = true;
+    }
+  }
+  relaxed
+}
+
+// Breaks bonds stretched past rupture_strain or pulling harder than rupture_force, then
+// bonds unbonded cells closer than capture_dist at their current distance, with spring_k times
+// the adhesion of their species. Pairs whose bond broke in the same step aren't captured.
+fn update_bonds(t: f64, y: &mut [cell::Cell], settings: &settings::Settings) -> Result<bool, IntegratorError> {
+  if settings.rupture_strain <= 0.0 && settings.rupture_force <= 0.0 && settings.capture_dist <= 0.0 {
+    return Ok(false);
+  }
+
+  let mut broken: Vec<(usize, usize)> = vec![];
+  for (i, cell_a) in y.iter().enumerate() {
+    for link in &cell_a.links {
+      let j = link.parties.1;
+      if j < i {
+        continue;
+      }
+      let stretch = separation(&cell_a.pos, &y[j].pos, t, settings).norm() - link.relax_length;
+      let over_strain = settings.rupture_strain > 0.0 && link.relax_length > 0.0
+        && stretch / link.relax_length > settings.rupture_strain;
+      let over_force = settings.rupture_force > 0.0 && stiffness(link, cell_a.nutrient, y[j].nutrient, settings) * stretch > settings.rupture_force;
+      if over_strain || over_force {
+        broken.push((i, j));
+      }
+    }
+  }
+  for (i, j) in &broken {
+    y[*i].links.retain(|link| link.parties.1 != *j);
+    y[*j].links.retain(|link| link.parties.1 != *i);
+    y[*i].angles.retain(|angle| angle.parties.1 != *j && angle.parties.2 != *j);
+    y[*j].angles.retain(|angle| angle.parties.1 != *i && angle.parties.2 != *i);
+    y[*i].broken += 1;
+    y[*j].broken += 1;
+  }
+
+  let mut formed: Vec<(usize, usize, f64, f64)> = vec![];
+  if settings.capture_dist > 0.0 {
+    let tree = cell_tree(t, y, settings)?;
+    for (i, cell_a) in y.iter().enumerate() {
+      let mut close = tree.get_within(cell_a.pos.x, cell_a.pos.y, settings.capture_dist);
+      close.sort_unstable();
+      close.dedup();
+      for j in close {
+        if j <= i || broken.contains(&(i, j)) {
+          continue;
+        }
+        let cell_b = &y[j];
+        let dist = separation(&cell_a.pos, &cell_b.pos, t, settings).norm();
+        let adhesion = species::interaction(settings, cell_a.species, cell_b.species).adhesion;
+        if dist > 0.0 && dist <= settings.capture_dist && adhesion > 0.0
+          && !cell_a.links.iter().any(|link| link.parties.1 == j) {
+          formed.push((i, j, dist, settings.spring_k * adhesion));
+        }
+      }
+    }
+  }
+  for (i, j, dist, k) in &formed {
+    y[*i].links.push(cell::ForceLink::new(*i, *j, *k, *dist, settings.bond_type));
+    y[*j].links.push(cell::ForceLink::new(*j, *i, *k, *dist, settings.bond_type));
+  }
+
+  Ok(!broken.is_empty() || !formed.is_empty())
 }
 
 pub fn euler(
-    grid: &[cell::Cell],
+    start: Checkpoint,
     dt: f64,
-    dy: fn(f64, &mut [cell::Cell], &settings::Settings) -> Vec<f64>,
-    settings: &settings::Settings
-) -> Vec<(i32, f64, Vec<cell::Cell>)> {
-  let mut path: Vec<(i32, f64, Vec<cell::Cell>)> = vec![];
-  let mut state = vec![cell::Cell::new(0.0, 0.0, 0.0) ; grid.len()];
-  state.clone_from_slice(grid);
-
-  let mut time = 0.0;
-  let mut iter = 0;
+    dy: Derivs,
+    settings: &settings::Settings,
+    observer: &mut dyn Observer
+) -> Result<(), IntegratorError> {
+  let mut state = start.state;
+  let mut field = start.field;
+  let mut time = start.time;
+  let mut iter = start.iter;
+  if iter == 0 {
+    observer.observe(iter, time, &state, field.as_ref())?;
+  }
 
   while time < settings.del_t {
-    path.push((iter, time, state.clone()));
-    let change = dy(time, &mut state, &settings);
+    let change = dy(time, &mut state, &settings)?;
 
     for i in 0..state.len() {
       let mut cell = &mut state[i];
@@ -151,122 +662,142 @@ pub fn euler(
 
     time += dt;
     iter += 1;
+    after_step(time, dt, &mut state, &mut field, settings)?;
+    observer.observe(iter, time, &state, field.as_ref())?;
+    if observer.wants_checkpoint(time) {
+      observer.checkpoint(&Checkpoint { field: field.clone(), ..Checkpoint::at(iter, time, &state) })?;
+    }
   }
-  path.push((iter, time, state));
-  path
+
+  Ok(())
 }
 
 pub fn rk(
-  grid: &[cell::Cell],
+  start: Checkpoint,
   dt: f64,
-  dy: fn(f64, &mut [cell::Cell], &settings::Settings) -> Vec<f64>,
-  settings: &settings::Settings
-) -> Vec<(i32, f64, Vec<cell::Cell>)> {
-  let mut path: Vec<(i32, f64, Vec<cell::Cell>)> = vec![];
-  let mut state = vec![cell::Cell::new(0.0, 0.0, 0.0); grid.len()];
-  state.clone_from_slice(grid);
-
-  let mut time = 0.0;
-  let mut iter = 0;
+  dy: Derivs,
+  settings: &settings::Settings,
+  observer: &mut dyn Observer
+) -> Result<(), IntegratorError> {
+  let mut state = start.state;
+  let mut field = start.field;
+  let mut time = start.time;
+  let mut iter = start.iter;
+  if iter == 0 {
+    observer.observe(iter, time, &state, field.as_ref())?;
+  }
 
   while time < settings.del_t {
-    path.push((iter, time, state.clone()));
+    let start = state.clone();
 
-    let k1 = dy(time, &mut state, &settings);
+    let k1 = dy(time, &mut state, &settings)?;
     for (i, cell) in state.iter_mut().enumerate() {
       cell.pos.x += dt * k1[i*2] / 2.0;
       cell.pos.y += dt * k1[i*2+1] / 2.0;
     }
 
-    let k2 = dy(time + dt/2.0, &mut state, &settings);
+    let k2 = dy(time + dt/2.0, &mut state, &settings)?;
     for (i, cell) in state.iter_mut().enumerate() {
-      cell.pos.x = path[path.len() - 1].2[i].pos.x + dt * k2[i*2] / 2.0;
-      cell.pos.y = path[path.len() - 1].2[i].pos.y + dt * k2[i*2+1] / 2.0;
+      cell.pos.x = start[i].pos.x + dt * k2[i*2] / 2.0;
+      cell.pos.y = start[i].pos.y + dt * k2[i*2+1] / 2.0;
     }
 
-    let k3 = dy(time + dt/2.0, &mut state, &settings);
+    let k3 = dy(time + dt/2.0, &mut state, &settings)?;
     for (i, cell) in state.iter_mut().enumerate() {
-      cell.pos.x = path[path.len() - 1].2[i].pos.x + dt * k3[i*2];
-      cell.pos.y = path[path.len() - 1].2[i].pos.y + dt * k3[i*2+1];
+      cell.pos.x = start[i].pos.x + dt * k3[i*2];
+      cell.pos.y = start[i].pos.y + dt * k3[i*2+1];
     }
 
-    let k4 = dy(time + dt, &mut state, &settings);
+    let k4 = dy(time + dt, &mut state, &settings)?;
 
     for (i, cell) in state.iter_mut().enumerate() {
-      cell.pos.x = path[path.len() - 1].2[i].pos.x + (1.0/6.0) * dt * (k1[i*2] + 2.0*k2[i*2] + 2.0*k3[i*2] + k4[i*2]);
-      cell.pos.y = path[path.len() - 1].2[i].pos.y + (1.0/6.0) * dt * (k1[i*2+1] + 2.0*k2[i*2+1] + 2.0*k3[i*2+1] + k4[i*2+1]);
+      cell.pos.x = start[i].pos.x + (1.0/6.0) * dt * (k1[i*2] + 2.0*k2[i*2] + 2.0*k3[i*2] + k4[i*2]);
+      cell.pos.y = start[i].pos.y + (1.0/6.0) * dt * (k1[i*2+1] + 2.0*k2[i*2+1] + 2.0*k3[i*2+1] + k4[i*2+1]);
     }
     time += dt;
     iter += 1;
+    after_step(time, dt, &mut state, &mut field, settings)?;
+    observer.observe(iter, time, &state, field.as_ref())?;
+    if observer.wants_checkpoint(time) {
+      observer.checkpoint(&Checkpoint { field: field.clone(), ..Checkpoint::at(iter, time, &state) })?;
+    }
   }
-  path.push((iter, time, state));
-  path
+
+  Ok(())
 }
 
 pub fn predictor_corrector(
-  grid: &[cell::Cell],
+  start: Checkpoint,
   dt: f64,
-  dy: fn(f64, &mut [cell::Cell], &settings::Settings) -> Vec<f64>,
-  settings: &settings::Settings
-) -> Vec<(i32, f64, Vec<cell::Cell>)> {
-  let mut path: Vec<(i32, f64, Vec<cell::Cell>)> = vec![];
-  let mut state = vec![cell::Cell::new(0.0, 0.0, 0.0); grid.len()];
-  state.clone_from_slice(grid);
-  path.push((0, 0.0, state.clone()));
-
-  let mut time = 0.0;
+  dy: Derivs,
+  settings: &settings::Settings,
+  observer: &mut dyn Observer
+) -> Result<(), IntegratorError> {
+  // Only the last four states are needed by the Adams method
+  let mut history = start.history;
+  let mut state = start.state;
+  let mut field = start.field;
+  let mut time = start.time;
+  let mut iter = start.iter;
+  if iter == 0 {
+    observer.observe(0, 0.0, &state, field.as_ref())?;
+    history.push((0.0, state.clone()));
+  }
 
   // Get first 3 states with standard Runge-Kutta Method (assumes dt*3 < settings.del_t)
-  for i in 0..3 {
-    state = path[i].2.clone();
-    let mut k1 = dy(time, &mut state, settings);
+  for i in history.len()-1..3 {
+    let start = history[i].1.clone();
+    state = start.clone();
+    let mut k1 = dy(time, &mut state, settings)?;
     k1.iter_mut().for_each(|k| {*k *= dt;});
 
     state.iter_mut().enumerate().for_each(|(ind, c)| {
-      c.pos.x = path[i].2[ind].pos.x + 0.5*k1[ind*2];
-      c.pos.y = path[i].2[ind].pos.y + 0.5*k1[ind*2+1];
+      c.pos.x = start[ind].pos.x + 0.5*k1[ind*2];
+      c.pos.y = start[ind].pos.y + 0.5*k1[ind*2+1];
     });
-    let mut k2 = dy(time + dt*0.5, &mut state, &settings);
+    let mut k2 = dy(time + dt*0.5, &mut state, &settings)?;
     k2.iter_mut().for_each(|k| {*k *= dt;});
 
     state.iter_mut().enumerate().for_each(|(ind, c)| {
-      c.pos.x = path[i].2[ind].pos.x + 0.5*k2[ind*2];
-      c.pos.y = path[i].2[ind].pos.y + 0.5*k2[ind*2];
+      c.pos.x = start[ind].pos.x + 0.5*k2[ind*2];
+      c.pos.y = start[ind].pos.y + 0.5*k2[ind*2+1];
     });
-    let mut k3 = dy(time + dt*0.5, &mut state, &settings);
+    let mut k3 = dy(time + dt*0.5, &mut state, &settings)?;
     k3.iter_mut().for_each(|k| {*k *= dt;});
 
     state.iter_mut().enumerate().for_each(|(ind, c)| {
-      c.pos.x = path[i].2[ind].pos.x + k3[ind];
-      c.pos.y = path[i].2[ind].pos.y + k3[ind];
+      c.pos.x = start[ind].pos.x + k3[ind*2];
+      c.pos.y = start[ind].pos.y + k3[ind*2+1];
     });
-    let mut k4 = dy(time + dt, &mut state, &settings);
+    let mut k4 = dy(time + dt, &mut state, &settings)?;
     k4.iter_mut().for_each(|k| {*k*=dt;});
 
     state.iter_mut().enumerate().for_each(|(ind, c)| {
-      c.pos.x = path[i].2[ind].pos.x + (k1[ind*2] + 2.0*k2[ind*2] + 2.0*k3[ind*2] + k4[ind*2]) / 6.0;
-      c.pos.y = path[i].2[ind].pos.y + (k1[ind*2+1] + 2.0*k2[ind*2+1] + 2.0*k3[ind*2+1] + k4[ind*2+1]) / 6.0;
+      c.pos.x = start[ind].pos.x + (k1[ind*2] + 2.0*k2[ind*2] + 2.0*k3[ind*2] + k4[ind*2]) / 6.0;
+      c.pos.y = start[ind].pos.y + (k1[ind*2+1] + 2.0*k2[ind*2+1] + 2.0*k3[ind*2+1] + k4[ind*2+1]) / 6.0;
     });
     time += dt;
-    path.push(((i+1) as i32, time, state.clone()));
+    iter += 1;
+    if after_step(time, dt, &mut state, &mut field, settings)? {
+      relink(&mut history, &state);
+    }
+    observer.observe(iter, time, &state, field.as_ref())?;
+    history.push((time, state.clone()));
   }
 
-  let mut iter = 5;
-
-
   // Do Adams fourth-order predictor-corrector method
   while time < settings.del_t {
     time += dt;
 
-    let mut state1 = path[path.len() - 1].2.clone(); // w3
-    let mut state2 = path[path.len() - 2].2.clone(); // w2
-    let mut state3 = path[path.len() - 3].2.clone(); // w1
-    let mut state4 = path[path.len() - 4].2.clone(); // w0
+    let mut state1 = history[3].1.clone(); // w3
+    let mut state2 = history[2].1.clone(); // w2
+    let mut state3 = history[1].1.clone(); // w1
+    let mut state4 = history[0].1.clone(); // w0
 
-    let f1 = dy(path[path.len() - 1].1, &mut state1, &settings);
-    let f2 = dy(path[path.len() - 2].1, &mut state2, &settings);
-    let f3 = dy(path[path.len() - 3].1, &mut state3, &settings);
-    let f4 = dy(path[path.len() - 4].1, &mut state4, &settings);
+    let f1 = dy(history[3].0, &mut state1, &settings)?;
+    let f2 = dy(history[2].0, &mut state2, &settings)?;
+    let f3 = dy(history[1].0, &mut state3, &settings)?;
+    let f4 = dy(history[0].0, &mut state4, &settings)?;
 
     // Predictor
     state.iter_mut().enumerate().for_each(|(ind, c)| {
@@ -274,72 +805,114 @@ pub fn predictor_corrector(
       c.pos.y = state1[ind].pos.y + dt * (55.0 * f1[ind*2+1] - 59.0 * f2[ind*2+1] + 37.0*f3[ind*2+1] - 9.0*f4[ind*2+1]) / 24.0;
     });
     // Corrector
-    let f = dy(time, &mut state, &settings);
+    let f = dy(time, &mut state, &settings)?;
     state.iter_mut().enumerate().for_each(|(ind, c)| {
       c.pos.x = state1[ind].pos.x + dt * (9.0 * f[ind*2] + 19.0*f1[ind*2] - 5.0*f2[ind*2] + f3[ind*2])/24.0;
       c.pos.y = state1[ind].pos.y + dt * (9.0 * f[ind*2+1] + 19.0*f1[ind*2+1] - 5.0*f2[ind*2+1] + f3[ind*2+1])/24.0;
     });
-    path.push((iter, time, state.clone()));
     iter += 1;
+    if after_step(time, dt, &mut state, &mut field, settings)? {
+      relink(&mut history, &state);
+    }
+    observer.observe(iter, time, &state, field.as_ref())?;
+    history.remove(0);
+    history.push((time, state.clone()));
+    if observer.wants_checkpoint(time) {
+      observer.checkpoint(&Checkpoint { history: history.clone(), field: field.clone(), ..Checkpoint::at(iter, time, &state) })?;
+    }
   }
 
-  path
+  Ok(())
+}
+
+// Gives the earlier states kept by multistep methods the bonds of the current state, so
+// their derivatives come from the same forces. Bonds they already had keep their old rest lengths.
+// Cells born since are added as if their mothers had already divided then, with today's bonds.
+fn relink(history: &mut [(f64, Vec<cell::Cell>)], state: &[cell::Cell]) {
+  for (_t, past) in history.iter_mut() {
+    for (n, cell) in state.iter().enumerate().skip(past.len()) {
+      let m = cell.parent.expect("Cells added to the grid have a parent");
+      let half = cell::Pos{x: 0.5 * (cell.pos.x - state[m].pos.x), y: 0.5 * (cell.pos.y - state[m].pos.y)};
+      let mut daughter = past[m].clone();
+      daughter.pos = cell::Pos{x: past[m].pos.x + half.x, y: past[m].pos.y + half.y};
+      daughter.radius = cell.radius;
+      daughter.mass = cell.mass;
+      daughter.links = vec![];
+      daughter.parent = cell.parent;
+      past[m].pos = cell::Pos{x: past[m].pos.x - half.x, y: past[m].pos.y - half.y};
+      past[m].radius = state[m].radius;
+      past[m].mass = state[m].mass;
+      // Bonds of the mother were handed out and resized, so they all come from the current state
+      past[m].links.clear();
+      for other in past.iter_mut() {
+        other.links.retain(|link| link.parties.1 != m);
+      }
+      debug_assert_eq!(past.len(), n);
+      past.push(daughter);
+    }
+
+    for (past, cell) in past.iter_mut().zip(state) {
+      past.links = cell.links.iter().map(|link| {
+        past.links.iter().find(|old| old.parties.1 == link.parties.1).unwrap_or(link).clone()
+      }).collect();
+      past.angles = cell.angles.clone();
+    }
+  }
 }
 
 pub fn rk_adaptive(
-  grid: &[cell::Cell],
+  start: Checkpoint,
   tol: f64,
-  dy: fn(f64, &mut [cell::Cell], &settings::Settings) -> Vec<f64>,
-  settings: &settings::Settings
-) -> Vec<(i32, f64, Vec<cell::Cell>)> {
-  let mut path: Vec<(i32, f64, Vec<cell::Cell>)> = vec![];
-  let mut state = vec![cell::Cell::new(0.0, 0.0, 0.0) ; grid.len()];
-  state.clone_from_slice(grid);
-
-  let mut time = 0.0;
-  let mut iter = 0;
-  let mut iter_last = -1;
+  dy: Derivs,
+  settings: &settings::Settings,
+  observer: &mut dyn Observer
+) -> Result<(), IntegratorError> {
+  let mut state = start.state;
+  let mut field = start.field;
+  let mut time = start.time;
+  let mut iter = start.iter;
+  if iter == 0 {
+    observer.observe(iter, time, &state, field.as_ref())?;
+  }
 
-  let mut dt = 0.01;
+  let mut dt = start.dt.unwrap_or(0.01);
 
   while time < settings.del_t {
-    if iter_last != iter {
-      path.push((iter, time, state.clone()));
-    }
-    iter_last = iter;
+    let start = state.clone();
 
     let estimate = |dt: f64, state: &mut Vec<cell::Cell>| {
-      let k1 = dy(time, state, &settings);
+      let k1 = dy(time, state, &settings)?;
       for (i, cell) in state.iter_mut().enumerate() {
         cell.pos.x += dt * k1[i*2] / 2.0;
         cell.pos.y += dt * k1[i*2+1] / 2.0;
       }
 
-      let k2 = dy(time + dt/2.0, state, &settings);
+      let k2 = dy(time + dt/2.0, state, &settings)?;
       for (i, cell) in state.iter_mut().enumerate() {
-        cell.pos.x = path[path.len() - 1].2[i].pos.x + dt * k2[i*2] / 2.0;
-        cell.pos.y = path[path.len() - 1].2[i].pos.y + dt * k2[i*2+1] / 2.0;
+        cell.pos.x = start[i].pos.x + dt * k2[i*2] / 2.0;
+        cell.pos.y = start[i].pos.y + dt * k2[i*2+1] / 2.0;
       }
 
-      let k3 = dy(time + dt/2.0, state, &settings);
+      let k3 = dy(time + dt/2.0, state, &settings)?;
       for (i, cell) in state.iter_mut().enumerate() {
-        cell.pos.x = path[path.len() - 1].2[i].pos.x + dt * k3[i*2];
-        cell.pos.y = path[path.len() - 1].2[i].pos.y + dt * k3[i*2+1];
+        cell.pos.x = start[i].pos.x + dt * k3[i*2];
+        cell.pos.y = start[i].pos.y + dt * k3[i*2+1];
       }
 
-      let k4 = dy(time + dt, state, &settings);
+      let k4 = dy(time + dt, state, &settings)?;
 
       for (i, cell) in state.iter_mut().enumerate() {
-        cell.pos.x = path[path.len() - 1].2[i].pos.x + (1.0/6.0) * dt * (k1[i*2] + 2.0*k2[i*2] + 2.0*k3[i*2] + k4[i*2]);
-        cell.pos.y = path[path.len() - 1].2[i].pos.y + (1.0/6.0) * dt * (k1[i*2+1] + 2.0*k2[i*2+1] + 2.0*k3[i*2+1] + k4[i*2+1]);
+        cell.pos.x = start[i].pos.x + (1.0/6.0) * dt * (k1[i*2] + 2.0*k2[i*2] + 2.0*k3[i*2] + k4[i*2]);
+        cell.pos.y = start[i].pos.y + (1.0/6.0) * dt * (k1[i*2+1] + 2.0*k2[i*2+1] + 2.0*k3[i*2+1] + k4[i*2+1]);
       }
+      Ok(())
     };
 
-    let mut full_step = path[path.len() - 1].2.clone();
-    estimate(dt, &mut full_step);
+    let mut full_step = start.clone();
+    estimate(dt, &mut full_step)?;
 
-    let mut half_step = path[path.len() - 1].2.clone();
-    estimate(dt * 0.5, &mut half_step);
+    let mut half_step = start.clone();
+    estimate(dt * 0.5, &mut half_step)?;
 
     let error = half_step.iter().zip(full_step.iter()).map(|(half, full)| {
       let err_x = half.pos.x - full.pos.x;
@@ -349,12 +922,15 @@ pub fn rk_adaptive(
 
     if error > tol { // half step size if error is bad
       dt *= 0.5;
+      if time + dt == time {
+        return Err(IntegratorError::StepUnderflow{time, dt});
+      }
       continue;
     }
 
     // Check to see if you should double step size
-    let mut double_step = path[path.len() - 1].2.clone();
-    estimate(dt * 2.0, &mut double_step);
+    let mut double_step = start.clone();
+    estimate(dt * 2.0, &mut double_step)?;
 
     let error = half_step.iter().zip(double_step.iter()).map(|(half, full)| {
       let err_x = half.pos.x - full.pos.x;
@@ -371,29 +947,35 @@ pub fn rk_adaptive(
 
     time += dt;
     iter += 1;
+    after_step(time, dt, &mut state, &mut field, settings)?;
+    observer.observe(iter, time, &state, field.as_ref())?;
+    if observer.wants_checkpoint(time) {
+      observer.checkpoint(&Checkpoint { dt: Some(dt), field: field.clone(), ..Checkpoint::at(iter, time, &state) })?;
+    }
   }
-  path.push((iter, time, state));
-  path
+
+  Ok(())
 }
 
 pub fn rk45(
-  grid: &[cell::Cell],
+  start: Checkpoint,
   epsilon: f64,
   dt_min: f64,
   dt_max: f64,
-  dy: fn(f64, &mut [cell::Cell], &settings::Settings) -> Vec<f64>,
-  settings: &settings::Settings
-) -> Vec<(i32, f64, Vec<cell::Cell>)> {
-  let mut path: Vec<(i32, f64, Vec<cell::Cell>)> = vec![];
-
-  let mut time = 0.0;
-  let mut iter = 0;
-  let mut last_iter = -1;
-
-  let mut state = vec![cell::Cell::new(0.0, 0.0, 0.0) ; grid.len()];
-  state.clone_from_slice(grid);
+  dy: Derivs,
+  settings: &settings::Settings,
+  observer: &mut dyn Observer
+) -> Result<(), IntegratorError> {
+  let mut state = start.state;
+  let mut field = start.field;
+  let mut time = start.time;
+  let mut iter = start.iter;
+  if iter == 0 {
+    observer.observe(iter, time, &state, field.as_ref())?;
+  }
 
-  let mut dt = 0.01;
+  let mut dt = start.dt.unwrap_or(0.01);
+  let mut start = state.clone();
 
   // coeffecients for RK4(5)
   let a = [0.0, 1.0/4.0, 3.0/8.0, 12.0/13.0, 1.0, 1.0/2.0];
@@ -407,77 +989,76 @@ pub fn rk45(
   let d = [25.0/216.0, 0.0, 1408.0/2565.0, 2197.0/4104.0, -1.0/5.0, 0.0];
 
   while time < settings.del_t {
-    // Only push state if we actually took a step
-    if last_iter != iter {
-      path.push((iter, time, state.clone()));
-    }
-    last_iter = iter;
-
-    let k1 = dy(time + dt*a[0], &mut state, &settings);
+    let k1 = dy(time + dt*a[0], &mut state, &settings)?;
     let k1: Vec<f64> = k1.iter().map(|k| dt*k).collect();
 
     for (i, cell) in state.iter_mut().enumerate() {
-      cell.pos.x = path[path.len() - 1].2[i].pos.x + b[1][0] * k1[i*2];
-      cell.pos.y = path[path.len() - 1].2[i].pos.y + b[1][0] * k1[i*2+1];
+      cell.pos.x = start[i].pos.x + b[1][0] * k1[i*2];
+      cell.pos.y = start[i].pos.y + b[1][0] * k1[i*2+1];
     }
-    let k2 = dy(time + dt*a[1], &mut state, &settings);
+    let k2 = dy(time + dt*a[1], &mut state, &settings)?;
     let k2: Vec<f64> = k2.iter().map(|k| dt*k).collect();
 
     for (i, cell) in state.iter_mut().enumerate() {
-      cell.pos.x = path[path.len() - 1].2[i].pos.x + b[2][0] * k1[i*2] + b[2][1] * k2[i*2];
-      cell.pos.y = path[path.len() - 1].2[i].pos.y + b[2][0] * k1[i*2+1] + b[2][1] * k2[i*2+1];
+      cell.pos.x = start[i].pos.x + b[2][0] * k1[i*2] + b[2][1] * k2[i*2];
+      cell.pos.y = start[i].pos.y + b[2][0] * k1[i*2+1] + b[2][1] * k2[i*2+1];
     }
-    let k3 = dy(time + dt*a[2], &mut state, &settings);
+    let k3 = dy(time + dt*a[2], &mut state, &settings)?;
     let k3: Vec<f64> = k3.iter().map(|k| dt*k).collect();
 
     for (i, cell) in state.iter_mut().enumerate() {
-      cell.pos.x = path[path.len() - 1].2[i].pos.x + b[3][0] * k1[i*2] + b[3][1] * k2[i*2] + b[3][2] * k3[i*2];
-      cell.pos.y = path[path.len() - 1].2[i].pos.y + b[3][0] * k1[i*2+1] + b[3][1] * k2[i*2+1] + b[3][2] * k3[i*2 + 1];
+      cell.pos.x = start[i].pos.x + b[3][0] * k1[i*2] + b[3][1] * k2[i*2] + b[3][2] * k3[i*2];
+      cell.pos.y = start[i].pos.y + b[3][0] * k1[i*2+1] + b[3][1] * k2[i*2+1] + b[3][2] * k3[i*2 + 1];
     }
-    let k4 = dy(time + dt*a[3], &mut state, &settings);
+    let k4 = dy(time + dt*a[3], &mut state, &settings)?;
     let k4: Vec<f64> = k4.iter().map(|k| dt*k).collect();
 
     for (i, cell) in state.iter_mut().enumerate() {
-      cell.pos.x = path[path.len() - 1].2[i].pos.x + b[4][0] * k1[i*2] + b[4][1] * k2[i*2] + b[4][2] * k3[i*2] + b[4][3] * k4[i*2];
-      cell.pos.y = path[path.len() - 1].2[i].pos.y + b[4][0] * k1[i*2+1] + b[4][1] * k2[i*2+1] + b[4][2] * k3[i*2 + 1] + b[4][3]*k4[i*2+1];
+      cell.pos.x = start[i].pos.x + b[4][0] * k1[i*2] + b[4][1] * k2[i*2] + b[4][2] * k3[i*2] + b[4][3] * k4[i*2];
+      cell.pos.y = start[i].pos.y + b[4][0] * k1[i*2+1] + b[4][1] * k2[i*2+1] + b[4][2] * k3[i*2 + 1] + b[4][3]*k4[i*2+1];
     }
-    let k5 = dy(time + dt*a[4], &mut state, &settings);
+    let k5 = dy(time + dt*a[4], &mut state, &settings)?;
     let k5: Vec<f64> = k5.iter().map(|k| dt*k).collect();
 
     for (i, cell) in state.iter_mut().enumerate() {
-      cell.pos.x += path[path.len() - 1].2[i].pos.x + b[5][0] * k1[i*2] + b[5][1] * k2[i*2] + b[5][2] * k3[i*2] + b[5][3] * k4[i*2] + b[5][4] * k5[i*2];
-      cell.pos.y += path[path.len() - 1].2[i].pos.y + b[5][0] * k1[i*2+1] + b[5][1] * k2[i*2+1] + b[5][2] * k3[i*2 + 1] + b[5][3]*k4[i*2+1] + b[5][4] * k5[i*2];
+      cell.pos.x = start[i].pos.x + b[5][0] * k1[i*2] + b[5][1] * k2[i*2] + b[5][2] * k3[i*2] + b[5][3] * k4[i*2] + b[5][4] * k5[i*2];
+      cell.pos.y = start[i].pos.y + b[5][0] * k1[i*2+1] + b[5][1] * k2[i*2+1] + b[5][2] * k3[i*2 + 1] + b[5][3]*k4[i*2+1] + b[5][4] * k5[i*2+1];
     }
-    let k6 = dy(time + dt*a[5], &mut state, &settings);
+    let k6 = dy(time + dt*a[5], &mut state, &settings)?;
     let k6: Vec<f64> = k6.iter().map(|k| dt*k).collect();
 
     let fifth_order: Vec<f64> = k1.iter().enumerate().map(|(i, k1)| {
       if i % 2 == 0 {
-        path[path.len() - 1].2[i/2].pos.x + c[0] * k1 + c[1] * k2[i] + c[2] * k3[i] + c[3] * k4[i] + c[4] * k5[i] + c[5]*k6[i]
+        start[i/2].pos.x + c[0] * k1 + c[1] * k2[i] + c[2] * k3[i] + c[3] * k4[i] + c[4] * k5[i] + c[5]*k6[i]
       } else {
-        path[path.len() - 1].2[i/2].pos.y + c[0] * k1 + c[1] * k2[i] + c[2] * k3[i] + c[3] * k4[i] + c[4] * k5[i] + c[5]*k6[i]
+        start[i/2].pos.y + c[0] * k1 + c[1] * k2[i] + c[2] * k3[i] + c[3] * k4[i] + c[4] * k5[i] + c[5]*k6[i]
       }
     }).collect();
     let fourth_order: Vec<f64> = k1.iter().enumerate().map(|(i, k1)| {
       if i % 2 == 0 {
-        path[path.len() - 1].2[i/2].pos.x + d[0] * k1 + d[1] * k2[i] + d[2] * k3[i] + d[3] * k4[i] + d[4] * k5[i] + d[5]*k6[i]
+        start[i/2].pos.x + d[0] * k1 + d[1] * k2[i] + d[2] * k3[i] + d[3] * k4[i] + d[4] * k5[i] + d[5]*k6[i]
       } else {
-        path[path.len() - 1].2[i/2].pos.y + d[0] * k1 + d[1] * k2[i] + d[2] * k3[i] + d[3] * k4[i] + d[4] * k5[i] + d[5]*k6[i]
+        start[i/2].pos.y + d[0] * k1 + d[1] * k2[i] + d[2] * k3[i] + d[3] * k4[i] + d[4] * k5[i] + d[5]*k6[i]
       }
     }).collect();
 
     state.iter_mut().enumerate().for_each(|(i, cell)| {
-      cell.pos.x = path[path.len() - 1].2[i].pos.x + d[0] * k1[i*2] + d[1] * k2[i*2] + d[2] * k3[i*2] + d[3] * k4[i*2] + d[4] * k5[i*2] + d[5]*k6[i*2];
-      cell.pos.y = path[path.len() - 1].2[i].pos.y + d[0] * k1[i*2+1] + d[1] * k2[i*2+1] + d[2] * k3[i*2+1] + d[3] * k4[i*2+1] + d[4] * k5[i*2+1] + d[5]*k6[i*2+1];
+      cell.pos.x = start[i].pos.x + d[0] * k1[i*2] + d[1] * k2[i*2] + d[2] * k3[i*2] + d[3] * k4[i*2] + d[4] * k5[i*2] + d[5]*k6[i*2];
+      cell.pos.y = start[i].pos.y + d[0] * k1[i*2+1] + d[1] * k2[i*2+1] + d[2] * k3[i*2+1] + d[3] * k4[i*2+1] + d[4] * k5[i*2+1] + d[5]*k6[i*2+1];
     });
 
     let error = fifth_order.iter().zip(fourth_order.iter()).map(|(five, four)| {
       (five - four).powi(2)
     }).sum::<f64>().sqrt();
 
-    if error <= epsilon || dt <= dt_min {
+    if error > epsilon && dt <= dt_min {
+      return Err(IntegratorError::StepUnderflow{time, dt});
+    }
+    let accepted = error <= epsilon;
+    if accepted {
       iter += 1;
       time += dt;
+      after_step(time, dt, &mut state, &mut field, settings)?;
     }
     dt *= 0.9 * (epsilon / error).powf(0.2);
     if dt > dt_max {
@@ -485,33 +1066,160 @@ pub fn rk45(
     } else if dt < dt_min {
       dt = dt_min;
     }
+
+    // Only observe state if we actually took a step
+    if accepted {
+      observer.observe(iter, time, &state, field.as_ref())?;
+      start = state.clone();
+      if observer.wants_checkpoint(time) {
+        observer.checkpoint(&Checkpoint { dt: Some(dt), field: field.clone(), ..Checkpoint::at(iter, time, &state) })?;
+      }
+    }
   }
-  path.push((iter, time, state));
-  path
+
+  Ok(())
 }
 
-// Return a vector containing state given and next 3 states
+// Dormand-Prince 5(4) with its fourth order continuous extension. States are observed at
+// multiples of sample_dt, interpolated inside the step that covers them, so the output
+// spacing does not depend on the step size. iter counts samples.
+#[allow(clippy::too_many_arguments)]
+pub fn dormand_prince(
+  start: Checkpoint,
+  epsilon: f64,
+  dt_min: f64,
+  dt_max: f64,
+  sample_dt: f64,
+  dy: Derivs,
+  settings: &settings::Settings,
+  observer: &mut dyn Observer
+) -> Result<(), IntegratorError> {
+  let mut state = start.state;
+  let mut field = start.field;
+  let mut time = start.time;
+  let mut iter = start.iter;
+  if iter == 0 {
+    observer.observe(iter, time, &state, field.as_ref())?;
+  }
+
+  let c = [0.0, 1.0/5.0, 3.0/10.0, 4.0/5.0, 8.0/9.0, 1.0, 1.0];
+  let a = [[0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            [1.0/5.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            [3.0/40.0, 9.0/40.0, 0.0, 0.0, 0.0, 0.0],
+            [44.0/45.0, -56.0/15.0, 32.0/9.0, 0.0, 0.0, 0.0],
+            [19372.0/6561.0, -25360.0/2187.0, 64448.0/6561.0, -212.0/729.0, 0.0, 0.0],
+            [9017.0/3168.0, -355.0/33.0, 46732.0/5247.0, 49.0/176.0, -5103.0/18656.0, 0.0],
+            [35.0/384.0, 0.0, 500.0/1113.0, 125.0/192.0, -2187.0/6784.0, 11.0/84.0]];
+  // Difference between the fifth and fourth order weights
+  let e = [71.0/57600.0, 0.0, -71.0/16695.0, 71.0/1920.0, -17253.0/339200.0, 22.0/525.0, -1.0/40.0];
+  // Weights of the continuous extension
+  let d = [-12715105075.0/11282082432.0, 0.0, 87487479700.0/32700410799.0, -10690763975.0/1880347072.0,
+            701980252875.0/199316789632.0, -1453857185.0/822651844.0, 69997945.0/29380423.0];
+
+  let samples = (settings.del_t / sample_dt + 1e-9).floor() as i32;
+  let end = samples as f64 * sample_dt;
+  let mut dt = start.dt.unwrap_or(dt_max);
+  // The last stage is evaluated at the new state, so it is the first stage of the next step
+  let mut k1 = if start.last_deriv.len() == state.len() * 2 {
+    start.last_deriv
+  } else {
+    dy(time, &mut state, settings)?
+  };
+
+  while iter < samples {
+    let step = dt.min(end - time);
+    let x0 = positions(&state);
+    let mut stage = state.clone();
+    let mut k = vec![k1.clone()];
+    for s in 1..7 {
+      let xs: Vec<f64> = x0.iter().enumerate().map(|(i, x)| {
+        x + step * k.iter().zip(&a[s]).map(|(k, a)| a * k[i]).sum::<f64>()
+      }).collect();
+      set_positions(&mut stage, &xs);
+      k.push(dy(time + c[s]*step, &mut stage, settings)?);
+    }
+
+    let error = (0..x0.len()).map(|i| {
+      (step * k.iter().zip(&e).map(|(k, e)| e * k[i]).sum::<f64>()).powi(2)
+    }).sum::<f64>().sqrt();
+
+    if error > epsilon && step <= dt_min {
+      return Err(IntegratorError::StepUnderflow{time, dt: step});
+    }
+    let accepted = error <= epsilon;
+    let q = if error == 0.0 { 5.0 } else { 0.9 * (epsilon / error).powf(0.2) };
+    dt = (step * q.clamp(0.2, 5.0)).clamp(dt_min, dt_max);
+    if !accepted {
+      continue;
+    }
+
+    // The new state is the last stage
+    let x1 = positions(&stage);
+    while iter < samples {
+      let t = (iter + 1) as f64 * sample_dt;
+      if t > time + step {
+        break;
+      }
+      let theta = (t - time) / step;
+      let dense: Vec<f64> = (0..x0.len()).map(|i| {
+        let r2 = x1[i] - x0[i];
+        let r3 = step * k[0][i] - r2;
+        let r4 = r2 - step * k[6][i] - r3;
+        let r5 = step * k.iter().zip(&d).map(|(k, d)| d * k[i]).sum::<f64>();
+        x0[i] + theta * (r2 + (1.0 - theta) * (r3 + theta * (r4 + (1.0 - theta) * r5)))
+      }).collect();
+      let mut sample = stage.clone();
+      set_positions(&mut sample, &dense);
+      iter += 1;
+      observer.observe(iter, t, &sample, field.as_ref())?;
+    }
+
+    time += step;
+    state = stage;
+    k1 = k.pop().unwrap();
+    let before = state.len();
+    if after_step(time, step, &mut state, &mut field, settings)? {
+      k1 = dy(time, &mut state, settings)?;
+    }
+    // Daughters start out touching, so the step size is found again from dt_min
+    if state.len() > before {
+      dt = dt_min;
+    }
+    if observer.wants_checkpoint(time) {
+      observer.checkpoint(&Checkpoint { dt: Some(dt), last_deriv: k1.clone(), field: field.clone(), ..Checkpoint::at(iter, time, &state) })?;
+    }
+  }
+
+  Ok(())
+}
+
+// Return a vector containing state given and next 3 states. If a cell divides on the way,
+// the vector starts over from the grid with the daughters.
 fn pca_rk4(
   mut time: f64,
   dt: f64,
-  dy: fn(f64, &mut [cell::Cell], &settings::Settings) -> Vec<f64>,
+  dy: Derivs,
   grid: &[cell::Cell],
+  field: &Option<nutrient::Field>,
   settings: &settings::Settings
-) -> Vec<(f64, Vec<cell::Cell>)> {
+) -> Result<Vec<(f64, Vec<cell::Cell>)>, IntegratorError> {
+  // The field of the states is only kept once they are accepted
+  let mut field = field.clone();
   let mut path: Vec<(f64, Vec<cell::Cell>)> = vec![];
   let mut state = vec![cell::Cell::new(0.0, 0.0, 0.0); grid.len()];
     state.clone_from_slice(grid);
   path.push((time, state.clone()));
 
-  for i in 0..3 {
-    let mut k1 = dy(time, &mut state, &settings);
+  while path.len() < 4 {
+    let i = path.len() - 1;
+    let mut k1 = dy(time, &mut state, &settings)?;
     k1.iter_mut().for_each(|k| {*k *= dt;});
 
     state.iter_mut().enumerate().for_each(|(ind, c)| {
       c.pos.x = path[i].1[ind].pos.x + k1[ind*2]/2.0;
       c.pos.y = path[i].1[ind].pos.y + k1[ind*2+1]/2.0;
     });
-    let mut k2 = dy(time + 0.5*dt, &mut state, &settings);
+    let mut k2 = dy(time + 0.5*dt, &mut state, &settings)?;
     k2.iter_mut().for_each(|k| {*k *= dt;});
 
 
@@ -519,14 +1227,14 @@ fn pca_rk4(
       c.pos.x = path[i].1[ind].pos.x + k2[ind*2]/2.0;
       c.pos.y = path[i].1[ind].pos.y + k2[ind*2+1]/2.0;
     });
-    let mut k3 = dy(time + 0.5*dt, &mut state, &settings);
+    let mut k3 = dy(time + 0.5*dt, &mut state, &settings)?;
     k3.iter_mut().for_each(|k| {*k *= dt;});
 
     state.iter_mut().enumerate().for_each(|(ind, c)| {
       c.pos.x = path[i].1[ind].pos.x + k3[ind*2];
       c.pos.y = path[i].1[ind].pos.y + k3[ind*2+1];
     });
-    let mut k4 = dy(time + dt, &mut state, &settings);
+    let mut k4 = dy(time + dt, &mut state, &settings)?;
     k4.iter_mut().for_each(|k| {*k *= dt;});
 
     state.iter_mut().enumerate().for_each(|(ind, c)| {
@@ -534,43 +1242,58 @@ fn pca_rk4(
       c.pos.y = path[i].1[ind].pos.y + (k1[ind*2+1] + 2.0*k2[ind*2+1] + 2.0*k3[ind*2+1] + k4[ind*2+1])/6.0;
     });
     time += dt;
+    let before = state.len();
+    if after_step(time, dt, &mut state, &mut field, settings)? {
+      if state.len() > before {
+        // Newborn cells have no past of their own, so the path starts over from them
+        path.clear();
+      } else {
+        relink(&mut path, &state);
+      }
+    }
     path.push((time, state.clone()));
   }
 
-  path
+  Ok(path)
 }
 
 pub fn predictor_corrector_adaptive(
-  grid: &[cell::Cell],
+  start: Checkpoint,
   epsilon: f64,
   dt_min: f64,
   dt_max: f64,
-  dy: fn(f64, &mut [cell::Cell], &settings::Settings) -> Vec<f64>,
-  settings: &settings::Settings
-) -> Vec<(i32, f64, Vec<cell::Cell>)> {
-  let mut path: Vec<(i32, f64, Vec<cell::Cell>)> = vec![];
-  let mut state = vec![cell::Cell::new(0.0, 0.0, 0.0) ; grid.len()];
-  state.clone_from_slice(grid);
-  path.push((0, 0.0, state));
-
-  let mut time = 0.0;
-  let mut dt = dt_max;
-  let mut last = false;
-
-  let mut considering = pca_rk4(time, dt, dy, &grid, settings);
-  let mut nflag = true;
-  time = considering.last().unwrap().0 + dt;
+  dy: Derivs,
+  settings: &settings::Settings,
+  observer: &mut dyn Observer
+) -> Result<(), IntegratorError> {
+  let mut iter = start.iter;
+  let mut last_time = start.time;
+  let mut field = start.field;
+  let mut dt = start.dt.unwrap_or(dt_max);
+  let mut last = start.last;
+  let mut nflag = start.nflag;
+
+  // Only the last four entries of considering are used by the Adams method
+  let mut considering = start.history;
+  if iter == 0 {
+    observer.observe(iter, last_time, &start.state, field.as_ref())?;
+    considering = pca_rk4(last_time, dt, dy, &start.state, &field, settings)?;
+    nflag = true;
+  }
 
   'out: loop {
+    // The next step always follows the newest state being considered
+    let time = considering[3].0 + dt;
+
     let mut state1 = considering[considering.len() - 1].1.clone();
     let mut state2 = considering[considering.len() - 2].1.clone();
     let mut state3 = considering[considering.len() - 3].1.clone();
     let mut state4 = considering[considering.len() - 4].1.clone();
 
-    let f1 = dy(considering[considering.len() - 1].0, &mut state1, settings);
-    let f2 = dy(considering[considering.len() - 2].0, &mut state2, settings);
-    let f3 = dy(considering[considering.len() - 3].0, &mut state3, settings);
-    let f4 = dy(considering[considering.len() - 4].0, &mut state4, settings);
+    let f1 = dy(considering[considering.len() - 1].0, &mut state1, settings)?;
+    let f2 = dy(considering[considering.len() - 2].0, &mut state2, settings)?;
+    let f3 = dy(considering[considering.len() - 3].0, &mut state3, settings)?;
+    let f4 = dy(considering[considering.len() - 4].0, &mut state4, settings)?;
 
     let mut wp = state1.clone();
     wp.iter_mut().enumerate().for_each(|(ind, c)| {
@@ -578,7 +1301,7 @@ pub fn predictor_corrector_adaptive(
       c.pos.y += dt / 24.0 * (55.0*f1[ind*2+1] - 59.0*f2[ind*2+1] + 37.0*f3[ind*2+1] - 9.0*f4[ind*2+1]);
     });
 
-    let f = dy(time, &mut wp, settings);
+    let f = dy(time, &mut wp, settings)?;
 
     let mut wc = state1.clone();
     wc.iter_mut().enumerate().for_each(|(ind, c)| {
@@ -595,13 +1318,26 @@ pub fn predictor_corrector_adaptive(
 
     if error <= epsilon {
       if nflag {
-        path.push((considering.len() as i32 - 4, considering[considering.len() - 4].0, considering[considering.len() - 4].1.clone()));
-        path.push((considering.len() as i32 - 3, considering[considering.len() - 3].0, considering[considering.len() - 3].1.clone()));
-        path.push((considering.len() as i32 - 2, considering[considering.len() - 2].0, considering[considering.len() - 2].1.clone()));
-        path.push((considering.len() as i32 - 1, considering[considering.len() - 1].0, considering[considering.len() - 1].1.clone()));
+        // The first of these was already observed as the starting state
+        for (t, state) in &considering[considering.len() - 4..] {
+          if *t > last_time {
+            // These states already hold the nutrient of the field stepped with them
+            if let Some(field) = field.as_mut() {
+              field.step(state, *t - last_time, settings);
+            }
+            iter += 1;
+            observer.observe(iter, *t, state, field.as_ref())?;
+            last_time = *t;
+          }
+        }
         nflag = false;
       }
-      path.push((considering.len() as i32, time, wc.clone()));
+      let before = wc.len();
+      let changed = after_step(time, dt, &mut wc, &mut field, settings)?;
+      iter += 1;
+      observer.observe(iter, time, &wc, field.as_ref())?;
+      last_time = time;
+      considering.remove(0);
       considering.push((time, wc.clone()));
 
       if last {
@@ -623,11 +1359,25 @@ pub fn predictor_corrector_adaptive(
           last = true;
         }
 
-        let nowconsidering = pca_rk4(time, dt, dy, &path[path.len() - 1].2, settings);
+        considering = pca_rk4(time, dt, dy, &wc, &field, settings)?;
         nflag = true;
-        for i in &nowconsidering {
-          considering.push(i.clone());
-        }
+      } else if wc.len() > before {
+        // Newborn cells have no past to extrapolate from
+        considering = pca_rk4(time, dt, dy, &wc, &field, settings)?;
+        nflag = true;
+      } else if changed {
+        relink(&mut considering, &wc);
+      }
+
+      if observer.wants_checkpoint(time) {
+        observer.checkpoint(&Checkpoint {
+          dt: Some(dt),
+          history: considering.clone(),
+          nflag,
+          last,
+          field: field.clone(),
+          ..Checkpoint::at(iter, time, &wc)
+        })?;
       }
     } else {
       let q = (epsilon / (2.0*error)).powf(0.25);
@@ -637,25 +1387,352 @@ pub fn predictor_corrector_adaptive(
         dt *= q;
       }
 
+      // Restart from the last accepted state, dropping unaccepted Runge-Kutta states
+      if nflag {
+        considering.truncate(considering.len() - 3);
+      }
+      let (start_time, start) = considering.pop().unwrap();
+
       if dt < dt_min {
-        panic!("Minimum exceeded");
+        return Err(IntegratorError::StepUnderflow{time: start_time, dt});
       }
+      considering = pca_rk4(start_time, dt, dy, &start, &field, settings)?;
+      nflag = true;
+    }
+  }
 
-      if nflag {
-        considering.remove(considering.len() - 1);
-        considering.remove(considering.len() - 1);
-        considering.remove(considering.len() - 1);
+  Ok(())
+}
+
+// Euler-Maruyama for overdamped Langevin dynamics, each free cell gets a random kick
+// of variance 2 * temperature * dt / damping per direction
+pub fn euler_maruyama(
+  start: Checkpoint,
+  dt: f64,
+  dy: Derivs,
+  settings: &settings::Settings,
+  observer: &mut dyn Observer
+) -> Result<(), IntegratorError> {
+  let mut state = start.state;
+  let mut field = start.field;
+  let mut time = start.time;
+  let mut iter = start.iter;
+  let mut rng = start.rng.unwrap_or_else(|| noise::Rng::new(settings.seed));
+  if iter == 0 {
+    observer.observe(iter, time, &state, field.as_ref())?;
+  }
+
+  // Kicks balance the friction of each cell, which the flow drag adds to
+  let amplitude = |cell: &cell::Cell| (2.0 * settings.temperature * dt / friction(cell, settings)).sqrt();
+
+  while time < settings.del_t {
+    let change = dy(time, &mut state, &settings)?;
+
+    for (i, cell) in state.iter_mut().enumerate() {
+      cell.pos.x += dt * change[i*2];
+      cell.pos.y += dt * change[i*2+1];
+      if !cell.fixed && cell.displace == forces::DispFunc::None {
+        let amplitude = amplitude(cell);
+        cell.pos.x += amplitude * rng.gaussian();
+        cell.pos.y += amplitude * rng.gaussian();
       }
-      let nowconsidering = pca_rk4(time, dt, dy, &path[path.len() - 1].2, settings);
-      for i in &nowconsidering {
-        considering.push(i.clone());
+    }
+
+    time += dt;
+    iter += 1;
+    after_step(time, dt, &mut state, &mut field, settings)?;
+    observer.observe(iter, time, &state, field.as_ref())?;
+    if observer.wants_checkpoint(time) {
+      observer.checkpoint(&Checkpoint { rng: Some(rng), field: field.clone(), ..Checkpoint::at(iter, time, &state) })?;
+    }
+  }
+
+  Ok(())
+}
+
+// Acceleration from net force and viscous damping, x and y interlaced. Fixed cells are held still
+// and displacement driven cells move with the velocity of their path.
+fn accel(t: f64, state: &mut [cell::Cell], force: Derivs, settings: &settings::Settings) -> Result<Vec<f64>, IntegratorError> {
+  let mut acc = force(t, state, settings)?;
+  for cell in state.iter_mut() {
+    if cell.displace != forces::DispFunc::None {
+      cell.vel = cell::Pos{x: forces::disp_velocity(&cell.displace, t, settings), y: 0.0};
+    } else if cell.fixed {
+      cell.vel = cell::Pos{x: 0.0, y: 0.0};
+    }
+  }
+
+  let dashpot = dashpot_forces(t, state, &cell_velocities(state), settings);
+  for (i, cell) in state.iter().enumerate() {
+    if cell.displace != forces::DispFunc::None || cell.fixed {
+      acc[i*2] = 0.0;
+      acc[i*2+1] = 0.0;
+    } else {
+      let flow = if settings.lees_edwards != forces::LeesEdwards::None { shear_flow(t, cell, settings) } else { 0.0 };
+      acc[i*2] = (acc[i*2] + dashpot[i*2] - settings.viscous * (cell.vel.x - flow)) / cell.mass;
+      acc[i*2+1] = (acc[i*2+1] + dashpot[i*2+1] - settings.viscous * cell.vel.y) / cell.mass;
+      if settings.flow != flow::Flow::None {
+        let drag = flow::drag(cell, settings);
+        let vel = flow::velocity(&cell.pos, settings);
+        acc[i*2] -= drag * (cell.vel.x - vel.x) / cell.mass;
+        acc[i*2+1] -= drag * (cell.vel.y - vel.y) / cell.mass;
       }
-      nflag = true;
     }
+  }
+  Ok(acc)
+}
+
+// Velocity Verlet for inertial grids, force gives the net force on each cell
+pub fn verlet(
+  start: Checkpoint,
+  dt: f64,
+  force: Derivs,
+  settings: &settings::Settings,
+  observer: &mut dyn Observer
+) -> Result<(), IntegratorError> {
+  let mut state = start.state;
+  let mut field = start.field;
+  let mut time = start.time;
+  let mut iter = start.iter;
+  if iter == 0 {
+    observer.observe(iter, time, &state, field.as_ref())?;
+  }
+
+  let mut acc = if start.last_deriv.len() == state.len() * 2 {
+    start.last_deriv
+  } else {
+    accel(time, &mut state, force, settings)?
+  };
+
+  while time < settings.del_t {
+    for (i, cell) in state.iter_mut().enumerate() {
+      cell.pos.x += dt * cell.vel.x + 0.5 * dt * dt * acc[i*2];
+      cell.pos.y += dt * cell.vel.y + 0.5 * dt * dt * acc[i*2+1];
+      cell.vel.x += 0.5 * dt * acc[i*2];
+      cell.vel.y += 0.5 * dt * acc[i*2+1];
+    }
+
+    // Viscous damping is evaluated with the half step velocity
     time += dt;
+    acc = accel(time, &mut state, force, settings)?;
+    for (i, cell) in state.iter_mut().enumerate() {
+      cell.vel.x += 0.5 * dt * acc[i*2];
+      cell.vel.y += 0.5 * dt * acc[i*2+1];
+    }
+    if after_step(time, dt, &mut state, &mut field, settings)? {
+      acc = accel(time, &mut state, force, settings)?;
+    }
+
+    iter += 1;
+    observer.observe(iter, time, &state, field.as_ref())?;
+    if observer.wants_checkpoint(time) {
+      observer.checkpoint(&Checkpoint { last_deriv: acc.clone(), field: field.clone(), ..Checkpoint::at(iter, time, &state) })?;
+    }
+  }
+
+  Ok(())
+}
+
+// Newton iterations stop once no position moves more than NEWTON_TOL
+const NEWTON_TOL: f64 = 1e-9;
+const NEWTON_ITER: usize = 20;
+// Relative residual and iteration cap of the inner linear solves
+const LINEAR_TOL: f64 = 1e-6;
+const LINEAR_ITER: usize = 200;
+
+// Positions of every cell, x and y interlaced
+fn positions(state: &[cell::Cell]) -> Vec<f64> {
+  state.iter().flat_map(|c| vec![c.pos.x, c.pos.y]).collect()
+}
+
+// Velocities of every cell as stored on inertial grids, x and y interlaced
+fn cell_velocities(state: &[cell::Cell]) -> Vec<f64> {
+  state.iter().flat_map(|c| vec![c.vel.x, c.vel.y]).collect()
+}
+
+fn set_positions(state: &mut [cell::Cell], pos: &[f64]) {
+  for (i, cell) in state.iter_mut().enumerate() {
+    cell.pos.x = pos[i*2];
+    cell.pos.y = pos[i*2+1];
   }
+}
 
-  path
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+  a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+fn norm(a: &[f64]) -> f64 {
+  dot(a, a).sqrt()
+}
+
+// Solves A x = b by BiCGSTAB, A is only known through its action on a vector
+pub fn bicgstab<E, F>(mut a: F, b: &[f64], tol: f64, max_iter: usize) -> Result<Vec<f64>, E>
+where F: FnMut(&[f64]) -> Result<Vec<f64>, E> {
+  let mut x = vec![0.0; b.len()];
+  let b_norm = norm(b);
+  if b_norm == 0.0 {
+    return Ok(x);
+  }
+
+  let mut r = b.to_vec();
+  let r0 = b.to_vec();
+  let mut p = vec![0.0; b.len()];
+  let mut v = vec![0.0; b.len()];
+  let (mut rho, mut alpha, mut omega) = (1.0, 1.0, 1.0);
+
+  for _ in 0..max_iter {
+    let rho_next = dot(&r0, &r);
+    if rho_next == 0.0 {
+      break;
+    }
+    let beta = (rho_next / rho) * (alpha / omega);
+    rho = rho_next;
+    p.iter_mut().zip(r.iter().zip(&v)).for_each(|(p, (r, v))| *p = r + beta * (*p - omega * v));
+
+    v = a(&p)?;
+    let r0_v = dot(&r0, &v);
+    if r0_v == 0.0 {
+      break;
+    }
+    alpha = rho / r0_v;
+    let s: Vec<f64> = r.iter().zip(&v).map(|(r, v)| r - alpha * v).collect();
+    if norm(&s) <= tol * b_norm {
+      x.iter_mut().zip(&p).for_each(|(x, p)| *x += alpha * p);
+      break;
+    }
+
+    let t = a(&s)?;
+    let t_t = dot(&t, &t);
+    omega = if t_t == 0.0 { 0.0 } else { dot(&t, &s) / t_t };
+    x.iter_mut().zip(p.iter().zip(&s)).for_each(|(x, (p, s))| *x += alpha * p + omega * s);
+    r = s.iter().zip(&t).map(|(s, t)| s - omega * t).collect();
+    if norm(&r) <= tol * b_norm || omega == 0.0 {
+      break;
+    }
+  }
+
+  Ok(x)
+}
+
+// Solves x - h * dy(time, x) = rhs for the positions x of the next state by Newton's method,
+// starting from guess. Jacobian-vector products are finite differences of dy, so the
+// Jacobian of the springs and repulsion is never formed. None if Newton's method does not converge.
+fn implicit_solve(
+  time: f64,
+  h: f64,
+  rhs: &[f64],
+  guess: &[cell::Cell],
+  dy: Derivs,
+  settings: &settings::Settings
+) -> Result<Option<Vec<cell::Cell>>, IntegratorError> {
+  let mut next = guess.to_vec();
+
+  for _ in 0..NEWTON_ITER {
+    let x = positions(&next);
+    let f = dy(time, &mut next, settings)?;
+    let residual: Vec<f64> = x.iter().zip(rhs.iter().zip(&f)).map(|(x, (rhs, f))| rhs + h * f - x).collect();
+
+    let x_norm = norm(&x);
+    let state = &next;
+    let jacobian = |v: &[f64]| {
+      let v_norm = norm(v);
+      if v_norm == 0.0 {
+        return Ok(vec![0.0; v.len()]);
+      }
+      let eps = f64::EPSILON.sqrt() * (1.0 + x_norm) / v_norm;
+      let shift: Vec<f64> = x.iter().zip(v).map(|(x, v)| x + eps * v).collect();
+      let mut shifted = state.to_vec();
+      set_positions(&mut shifted, &shift);
+      let f_shifted = dy(time, &mut shifted, settings)?;
+      Ok(v.iter().zip(f_shifted.iter().zip(&f)).map(|(v, (fs, f))| v - h * (fs - f) / eps).collect())
+    };
+    let delta = bicgstab(jacobian, &residual, LINEAR_TOL, LINEAR_ITER)?;
+
+    let x: Vec<f64> = x.iter().zip(&delta).map(|(x, d)| x + d).collect();
+    set_positions(&mut next, &x);
+    if delta.iter().all(|d| d.abs() <= NEWTON_TOL) {
+      return Ok(Some(next));
+    }
+  }
+
+  Ok(None)
+}
+
+// Backward Euler, or BDF2 with a backward Euler first step
+fn implicit(
+  start: Checkpoint,
+  dt: f64,
+  bdf2: bool,
+  dy: Derivs,
+  settings: &settings::Settings,
+  observer: &mut dyn Observer
+) -> Result<(), IntegratorError> {
+  let mut state = start.state;
+  let mut field = start.field;
+  let mut time = start.time;
+  let mut iter = start.iter;
+  let mut history = start.history;
+  if iter == 0 {
+    observer.observe(iter, time, &state, field.as_ref())?;
+  }
+
+  while time < settings.del_t {
+    let x = positions(&state);
+    let mut guess = state.clone();
+    let (rhs, h) = match history.last() {
+      Some((_t, prev)) if bdf2 => {
+        let prev = positions(prev);
+        let rhs: Vec<f64> = x.iter().zip(&prev).map(|(x, prev)| (4.0 * x - prev) / 3.0).collect();
+        let extrapolated: Vec<f64> = x.iter().zip(&prev).map(|(x, prev)| 2.0 * x - prev).collect();
+        set_positions(&mut guess, &extrapolated);
+        (rhs, 2.0 * dt / 3.0)
+      },
+      _ => (x, dt)
+    };
+
+    let next = match implicit_solve(time + dt, h, &rhs, &guess, dy, settings)? {
+      Some(next) => next,
+      None => return Err(IntegratorError::NoConvergence{time: time + dt})
+    };
+    let prev = std::mem::replace(&mut state, next);
+    if bdf2 {
+      history = vec![(time, prev)];
+    }
+
+    time += dt;
+    iter += 1;
+    if after_step(time, dt, &mut state, &mut field, settings)? && bdf2 {
+      relink(&mut history, &state);
+    }
+    observer.observe(iter, time, &state, field.as_ref())?;
+    if observer.wants_checkpoint(time) {
+      observer.checkpoint(&Checkpoint { history: history.clone(), field: field.clone(), ..Checkpoint::at(iter, time, &state) })?;
+    }
+  }
+
+  Ok(())
+}
+
+// Implicit first order method for stiff spring and repulsion parameters
+pub fn backward_euler(
+  start: Checkpoint,
+  dt: f64,
+  dy: Derivs,
+  settings: &settings::Settings,
+  observer: &mut dyn Observer
+) -> Result<(), IntegratorError> {
+  implicit(start, dt, false, dy, settings, observer)
+}
+
+// Implicit second order backward differentiation formula, keeps the previous state as history
+pub fn bdf2(
+  start: Checkpoint,
+  dt: f64,
+  dy: Derivs,
+  settings: &settings::Settings,
+  observer: &mut dyn Observer
+) -> Result<(), IntegratorError> {
+  implicit(start, dt, true, dy, settings, observer)
 }
 
 pub struct Stressavg {
@@ -663,40 +1740,53 @@ pub struct Stressavg {
   pub max_tension: f64,
   pub avg_stress: f64,
   pub avg_x: f64,
-  pub avg_y: f64
+  pub avg_y: f64,
+  pub shear_xy: f64, // xy stress of the whole grid over the unit box, positive when resisting shear to +x at the top
+  // Virial stress of self-propulsion about the center of the active cells over the unit box,
+  // kept out of the others. Negative when cells pull the film outward.
+  pub active_stress: f64,
+  pub active_x: f64,
+  pub active_y: f64
 }
 
-pub fn get_stress(grid: &mut Vec<cell::Cell>, t: f64, settings: &settings::Settings) -> Stressavg {
+pub fn get_stress(grid: &mut Vec<cell::Cell>, t: f64, settings: &settings::Settings) -> Result<Stressavg, IntegratorError> {
   let grid_old = grid.clone();
+  // Kelvin-Voigt dashpots add to the spring force in proportion to the stretching rate
+  let vel = if has_dashpots(grid) {
+    velocities(t, grid, settings)?
+  } else {
+    vec![]
+  };
 
   for cell in grid.iter_mut() {
     cell.tensor_stress = Some(cell::Stress{a: 0.0, b: 0.0, c: 0.0, d: 0.0});
     cell.stress = Some(0.0);
   }
 
+  let tip = indenter::tip(t, settings);
   let mut avgs = grid.par_iter_mut().enumerate().map(|(i, mut cell_a)| {
     let mut avgs = Stressavg{
       max_compression: 0.0,
       max_tension: 0.0,
       avg_stress: 0.0,
       avg_x: 0.0,
-      avg_y: 0.0
+      avg_y: 0.0,
+      shear_xy: 0.0,
+      active_stress: 0.0,
+      active_x: 0.0,
+      active_y: 0.0
     };
 
-    let new_tensor_stress = grid_old.iter().enumerate().map(|(j, cell_b)| {
-      if i == j {
-        return cell::Stress{a: 0.0, b: 0.0, c: 0.0, d: 0.0};
-      }
-
-      let direc = cell_b.pos.sub(&cell_a.pos);
+    let new_tensor_stress = cell_a.links.iter().map(|link| {
+      let direc = separation(&cell_a.pos, &grid_old[link.parties.1].pos, t, settings);
       let dist = direc.norm();
 
-      let mut force: f64 = 0.0;
-
-      if cell_a.neighbor_close.contains(&j) {
-        force = settings.spring_k * (dist - settings.spring_relax_close);
-      } else if cell_a.neighbor_far.contains(&j) {
-        force = settings.spring_k * (dist - settings.spring_relax_far);
+      let k = stiffness(link, cell_a.nutrient, grid_old[link.parties.1].nutrient, settings);
+      let mut force = k * (dist - link.relax_length);
+      if link.kind == cell::BondType::KelvinVoigt {
+        if let Some((_unit, rate)) = extension_rate(t, &grid_old, &vel, i, link.parties.1, settings) {
+          force += settings.kv_viscosity * rate;
+        }
       }
 
       if force.abs() < 1e-7 {
@@ -717,24 +1807,61 @@ pub fn get_stress(grid: &mut Vec<cell::Cell>, t: f64, settings: &settings::Setti
       }
     });
 
+    // Bending springs are only kept by their vertex, so they count twice against the halving below
+    let new_tensor_stress = cell_a.angles.iter().fold(new_tensor_stress, |acc, angle| {
+      let (_, first, second) = angle.parties;
+      let first = separation(&cell_a.pos, &grid_old[first].pos, t, settings);
+      let second = separation(&cell_a.pos, &grid_old[second].pos, t, settings);
+      match bend_forces(angle, first, second) {
+        Some((force_first, force_second)) => cell::Stress{
+          a: acc.a - 2.0 * (force_first.x * first.x + force_second.x * second.x),
+          b: acc.b - 2.0 * (force_first.y * first.x + force_second.y * second.x),
+          c: acc.c - 2.0 * (force_first.x * first.y + force_second.x * second.y),
+          d: acc.d - 2.0 * (force_first.y * first.y + force_second.y * second.y)
+        },
+        None => acc
+      }
+    });
+
     cell_a.tensor_stress = Some(new_tensor_stress);
 
+    // Walls, tethers and the indenter only push on the cells net_force moves
+    let mut force = cell::Pos{x: 0.0, y: 0.0};
+    if !cell_a.fixed && cell_a.displace == forces::DispFunc::None {
+      if settings.wall != forces::Wall::None || cell_a.force != forces::ForceFunc::None {
+        let wall = forces::wall_force(cell_a, settings);
+        force.x += wall.x;
+        force.y += wall.y;
+      }
+      if cell_a.tether.is_some() {
+        let tether = forces::tether_force(cell_a, settings);
+        force.x += tether.x;
+        force.y += tether.y;
+      }
+      if let Some(tip) = &tip {
+        let contact = tip.force(cell_a, settings);
+        force.x += contact.x;
+        force.y += contact.y;
+      }
+    }
     if cell_a.force != forces::ForceFunc::None {
       let force_func = forces::force_func(&cell_a.force);
-      let force = force_func(t, &mut cell_a, i, settings);
-      let force_mag = force.norm();
-
-      if force_mag > 1e-7 {
-        let ext_direc = cell::Pos{x: force.x / force_mag, y: force.y / force_mag};
-        let ext_direc = cell::Pos{x: ext_direc.x * cell_a.radius, y: ext_direc.y * cell_a.radius};
-        if let Some(stress) = cell_a.tensor_stress {
-          cell_a.tensor_stress = Some(
-            cell::Stress{
-              a: stress.a + force.x * ext_direc.x, b: stress.b + force.y * ext_direc.x,
-              c: stress.c + force.x * ext_direc.y, d: stress.d + force.y * ext_direc.y
-            }
-          );
-        }
+      let drive = force_func(t, &mut cell_a, i, settings);
+      force.x += drive.x;
+      force.y += drive.y;
+    }
+    let force_mag = force.norm();
+
+    if force_mag > 1e-7 {
+      let ext_direc = cell::Pos{x: force.x / force_mag, y: force.y / force_mag};
+      let ext_direc = cell::Pos{x: ext_direc.x * cell_a.radius, y: ext_direc.y * cell_a.radius};
+      if let Some(stress) = cell_a.tensor_stress {
+        cell_a.tensor_stress = Some(
+          cell::Stress{
+            a: stress.a + force.x * ext_direc.x, b: stress.b + force.y * ext_direc.x,
+            c: stress.c + force.x * ext_direc.y, d: stress.d + force.y * ext_direc.y
+          }
+        );
       }
     }
 
@@ -751,6 +1878,7 @@ pub fn get_stress(grid: &mut Vec<cell::Cell>, t: f64, settings: &settings::Setti
       avgs.avg_x = new_a;
       avgs.avg_y = new_b;
       avgs.avg_stress = new;
+      avgs.shear_xy = 0.25 * (stress.b + stress.c);
     }
 
     if let Some(stress) = cell_a.stress {
@@ -762,13 +1890,20 @@ pub fn get_stress(grid: &mut Vec<cell::Cell>, t: f64, settings: &settings::Setti
     }
 
     avgs
-  }).reduce(|| Stressavg {max_compression: 0.0, max_tension: 0.0, avg_stress: 0.0, avg_x: 0.0, avg_y: 0.0}, |acc, a| {
+  }).reduce(|| Stressavg {
+    max_compression: 0.0, max_tension: 0.0, avg_stress: 0.0, avg_x: 0.0, avg_y: 0.0, shear_xy: 0.0,
+    active_stress: 0.0, active_x: 0.0, active_y: 0.0
+  }, |acc, a| {
     let mut ret = Stressavg {
       max_compression: a.max_compression,
       max_tension: a.max_tension,
       avg_stress: acc.avg_stress + a.avg_stress,
       avg_x: acc.avg_x + a.avg_x,
-      avg_y: acc.avg_y + a.avg_y
+      avg_y: acc.avg_y + a.avg_y,
+      shear_xy: acc.shear_xy + a.shear_xy,
+      active_stress: 0.0,
+      active_x: 0.0,
+      active_y: 0.0
     };
 
     if acc.max_compression > ret.max_compression {
@@ -784,14 +1919,66 @@ pub fn get_stress(grid: &mut Vec<cell::Cell>, t: f64, settings: &settings::Setti
   avgs.avg_stress /= grid.len() as f64;
   avgs.avg_x /= grid.len() as f64;
   avgs.avg_y /= grid.len() as f64;
-  avgs
+  avgs.shear_xy /= PERIOD * PERIOD;
+
+  let active: Vec<&cell::Cell> = grid.iter().filter(|c| !c.fixed && c.displace == forces::DispFunc::None).collect();
+  if settings.active_force > 0.0 && !active.is_empty() {
+    let count = active.len() as f64;
+    let center_x = active.iter().map(|c| c.pos.x).sum::<f64>() / count;
+    let center_y = active.iter().map(|c| c.pos.y).sum::<f64>() / count;
+    for cell in active {
+      let force = forces::active_force(cell, settings);
+      avgs.active_x -= (cell.pos.x - center_x) * force.x / (PERIOD * PERIOD);
+      avgs.active_y -= (cell.pos.y - center_y) * force.y / (PERIOD * PERIOD);
+    }
+    avgs.active_stress = avgs.active_x + avgs.active_y;
+  }
+  Ok(avgs)
+}
+
+// Force the drive applies to displacement driven cells to hold them on their paths,
+// and their mean displacement
+#[derive(Default)]
+pub struct Reaction {
+  pub force: cell::Pos,
+  pub displacement: cell::Pos
+}
+
+pub fn get_reaction(grid: &[cell::Cell], t: f64, settings: &settings::Settings) -> Result<Reaction, IntegratorError> {
+  let internal = internal_forces(t, grid, settings)?;
+  let dashpot = if has_dashpots(grid) {
+    dashpot_forces(t, grid, &velocities(t, grid, settings)?, settings)
+  } else {
+    vec![0.0; grid.len() * 2]
+  };
+  let mut reaction = Reaction::default();
+  let mut driven = 0;
+
+  for (i, (cell, (force_x, force_y))) in grid.iter().zip(internal).enumerate() {
+    if cell.displace == forces::DispFunc::None {
+      continue;
+    }
+    reaction.force.x -= force_x + dashpot[i*2];
+    reaction.force.y -= force_y + dashpot[i*2+1];
+    let displacement = cell.pos.sub(&cell.initial_pos);
+    reaction.displacement.x += displacement.x;
+    reaction.displacement.y += displacement.y;
+    driven += 1;
+  }
+
+  if driven > 0 {
+    reaction.displacement.x /= driven as f64;
+    reaction.displacement.y /= driven as f64;
+  }
+  Ok(reaction)
 }
 
 pub struct Strainavg {
   pub maxdisplace: f64,
   pub maxxoff: f64,
   pub maxyoff: f64,
-  pub avgstrain: cell::Pos
+  pub avgstrain: cell::Pos,
+  pub msd: f64 // mean squared displacement from initial positions
 }
 
 pub fn get_strain(grid: &mut Vec<cell::Cell>, _t: f64) -> Strainavg {
@@ -800,7 +1987,8 @@ pub fn get_strain(grid: &mut Vec<cell::Cell>, _t: f64) -> Strainavg {
       maxdisplace: 0.0,
       maxxoff: 0.0,
       maxyoff: 0.0,
-      avgstrain: cell::Pos{x: 0.0, y: 0.0}
+      avgstrain: cell::Pos{x: 0.0, y: 0.0},
+      msd: 0.0
     };
 
     cell.strain = Some(cell.pos.sub(&cell.initial_pos));
@@ -812,10 +2000,11 @@ pub fn get_strain(grid: &mut Vec<cell::Cell>, _t: f64) -> Strainavg {
       avgs.avgstrain.y = strain.y;
       let norm = strain.norm();
       avgs.maxdisplace = norm;
+      avgs.msd = norm * norm;
     }
 
     avgs
-  }).reduce(|| Strainavg{maxdisplace: 0.0, maxxoff: 0.0, maxyoff: 0.0, avgstrain: cell::Pos{x: 0.0, y: 0.0}}, |acc, s| {
+  }).reduce(|| Strainavg{maxdisplace: 0.0, maxxoff: 0.0, maxyoff: 0.0, avgstrain: cell::Pos{x: 0.0, y: 0.0}, msd: 0.0}, |acc, s| {
     let mut avgs = Strainavg {
       maxdisplace: s.maxdisplace,
       maxxoff: s.maxxoff,
@@ -823,7 +2012,8 @@ pub fn get_strain(grid: &mut Vec<cell::Cell>, _t: f64) -> Strainavg {
       avgstrain: cell::Pos{
         x: acc.avgstrain.x + s.avgstrain.x,
         y: acc.avgstrain.y + s.avgstrain.y
-      }
+      },
+      msd: acc.msd + s.msd
     };
 
     if acc.maxxoff > avgs.maxxoff {
@@ -841,5 +2031,6 @@ pub fn get_strain(grid: &mut Vec<cell::Cell>, _t: f64) -> Strainavg {
 
   avgs.avgstrain.x /= grid.len() as f64;
   avgs.avgstrain.y /= grid.len() as f64;
+  avgs.msd /= grid.len() as f64;
   avgs
 }
@@ -0,0 +1,148 @@
+use serde::{Serialize, Deserialize};
+use crate::{cell, noise, settings};
+
+use super::RustfilmError;
+
+// Parameters of one species, relative to the global settings
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Species {
+  pub radius: f64, // times the generated radius, also scaling where the species divides
+  pub growth: f64, // times growth_rate
+}
+
+// Parameters between cells of two species, relative to the global settings
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Interaction {
+  pub spring_k: f64, // times spring_k for generated bonds and those made by division
+  pub repl_epsilon: f64, // times repl_epsilon
+  pub adhesion: f64, // times spring_k for bonds made by capture, none at 0
+}
+
+impl Default for Species {
+  fn default() -> Species {
+    Species { radius: 1.0, growth: 1.0 }
+  }
+}
+
+impl Default for Interaction {
+  fn default() -> Interaction {
+    Interaction { spring_k: 1.0, repl_epsilon: 1.0, adhesion: 1.0 }
+  }
+}
+
+// How generation assigns species to the cells
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Pattern {
+  Uniform, // every cell is species 0
+  Random, // species 0 but for species_fraction of the cells, spread over the other species
+  Core, // species 1 within core_radius of the center, species 0 in the shell around it
+  Stripes, // stripes of stripe_width across x, going through the species in order
+}
+
+pub fn pattern_enum(name: &str) -> Option<Pattern> {
+  match name {
+    "uniform" => Some(Pattern::Uniform),
+    "random" => Some(Pattern::Random),
+    "core" => Some(Pattern::Core),
+    "stripes" => Some(Pattern::Stripes),
+    _ => None
+  }
+}
+
+// Interaction between species a and b
+pub fn interaction(settings: &settings::Settings, a: usize, b: usize) -> &Interaction {
+  &settings.interactions[a * settings.species.len() + b]
+}
+
+// Species of a cell
+pub fn of<'a>(settings: &'a settings::Settings, cell: &cell::Cell) -> &'a Species {
+  &settings.species[cell.species]
+}
+
+// Gives each cell its species by species_pattern
+pub fn assign(grid: &mut [cell::Cell], settings: &settings::Settings) {
+  let count = settings.species.len();
+  // A stream apart from the one of the bond disorder
+  let mut rng = noise::Rng::new(!settings.seed);
+
+  for cell in grid.iter_mut() {
+    cell.species = match settings.species_pattern {
+      Pattern::Uniform => 0,
+      Pattern::Random => {
+        if count > 1 && rng.uniform() < settings.species_fraction {
+          1 + (rng.uniform() * (count - 1) as f64) as usize % (count - 1)
+        } else {
+          0
+        }
+      },
+      Pattern::Core => {
+        let from_center = ((cell.pos.x - 0.5).powi(2) + (cell.pos.y - 0.5).powi(2)).sqrt();
+        if from_center <= settings.core_radius { 1 } else { 0 }
+      },
+      Pattern::Stripes => (cell.pos.x / settings.stripe_width).floor().rem_euclid(count as f64) as usize,
+    };
+  }
+}
+
+// Reads a species table. Lines are
+//   species RADIUS GROWTH
+// for each species in order, and
+//   pair FIRST SECOND SPRING_K REPL_EPSILON ADHESION
+// for pairs that don't interact by the global settings. Blank lines and lines starting with
+// # are skipped.
+pub fn read_table(name: &str) -> Result<(Vec<Species>, Vec<Interaction>), RustfilmError> {
+  let contents = match std::fs::read_to_string(name) {
+    Ok(contents) => contents,
+    Err(_e) => return Err(RustfilmError{error: "Failed to read species table".to_string()})
+  };
+  let failed = || RustfilmError{error: "species table failed to parse".to_string()};
+
+  let mut species: Vec<Species> = vec![];
+  let mut pairs: Vec<(usize, usize, Interaction)> = vec![];
+  for line in contents.lines() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+    let words: Vec<&str> = line.split(|c: char| c == ',' || c.is_whitespace()).filter(|p| !p.is_empty()).collect();
+    let numbers: Result<Vec<f64>, _> = words[1..].iter().map(|p| p.parse::<f64>()).collect();
+    match (words[0], numbers) {
+      ("species", Ok(numbers)) if numbers.len() == 2 => {
+        species.push(Species { radius: numbers[0], growth: numbers[1] });
+      },
+      ("pair", Ok(numbers)) if numbers.len() == 5 => {
+        let index = |x: f64| if x >= 0.0 && x.fract() == 0.0 { Some(x as usize) } else { None };
+        match (index(numbers[0]), index(numbers[1])) {
+          (Some(first), Some(second)) => pairs.push((first, second, Interaction {
+            spring_k: numbers[2],
+            repl_epsilon: numbers[3],
+            adhesion: numbers[4]
+          })),
+          _ => return Err(failed())
+        }
+      },
+      _ => return Err(failed())
+    }
+  }
+
+  if species.is_empty() {
+    return Err(RustfilmError{error: "species table is empty".to_string()});
+  }
+  if species.iter().any(|s| s.radius <= 0.0 || s.growth < 0.0) {
+    return Err(RustfilmError{error: "species radius must be positive and growth nonnegative".to_string()});
+  }
+
+  let count = species.len();
+  let mut interactions = vec![Interaction::default(); count * count];
+  for (first, second, pair) in pairs {
+    if first >= count || second >= count {
+      return Err(RustfilmError{error: "species table pairs a species it doesn't list".to_string()});
+    }
+    if pair.spring_k < 0.0 || pair.repl_epsilon < 0.0 || pair.adhesion < 0.0 {
+      return Err(RustfilmError{error: "species table interactions must be nonnegative".to_string()});
+    }
+    interactions[first * count + second] = pair;
+    interactions[second * count + first] = pair;
+  }
+  Ok((species, interactions))
+}
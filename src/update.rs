@@ -7,6 +7,20 @@ pub enum UpdateFunc {
   Constrained,
   Sine,
   Pluck,
+  Step,
+  Ramp,
+  Square,
+  Triangle,
+  Chirp,
+  MultiSine,
+  Table,
+  DispRamp,
+  DispSine,
+  DispTable,
+  Shear,
+  Compress,
+  Biaxial,
+  Equibiaxial,
 }
 
 pub fn update(cell: &mut cell::Cell) {
@@ -15,6 +29,20 @@ pub fn update(cell: &mut cell::Cell) {
     UpdateFunc::Constrained => {},
     UpdateFunc::Sine => {},
     UpdateFunc::Pluck => pluck(cell),
+    UpdateFunc::Step => {},
+    UpdateFunc::Ramp => {},
+    UpdateFunc::Square => {},
+    UpdateFunc::Triangle => {},
+    UpdateFunc::Chirp => {},
+    UpdateFunc::MultiSine => {},
+    UpdateFunc::Table => {},
+    UpdateFunc::DispRamp => {},
+    UpdateFunc::DispSine => {},
+    UpdateFunc::DispTable => {},
+    UpdateFunc::Shear => {},
+    UpdateFunc::Compress => {},
+    UpdateFunc::Biaxial => {},
+    UpdateFunc::Equibiaxial => {},
   }
 }
 
@@ -24,6 +52,20 @@ pub fn func_enum(name: &str) -> UpdateFunc {
     "constrained" => UpdateFunc::Constrained,
     "sine" => UpdateFunc::Sine,
     "pluck" => UpdateFunc::Pluck,
+    "step" => UpdateFunc::Step,
+    "ramp" => UpdateFunc::Ramp,
+    "square" => UpdateFunc::Square,
+    "triangle" => UpdateFunc::Triangle,
+    "chirp" => UpdateFunc::Chirp,
+    "multisine" => UpdateFunc::MultiSine,
+    "table" => UpdateFunc::Table,
+    "disp_ramp" => UpdateFunc::DispRamp,
+    "disp_sine" => UpdateFunc::DispSine,
+    "disp_table" => UpdateFunc::DispTable,
+    "shear" => UpdateFunc::Shear,
+    "compress" => UpdateFunc::Compress,
+    "biaxial" => UpdateFunc::Biaxial,
+    "equibiaxial" => UpdateFunc::Equibiaxial,
     _ => UpdateFunc::None
   }
 }
@@ -34,6 +76,20 @@ pub fn enum_major(e: &UpdateFunc) -> Option<generation::Hook> {
     UpdateFunc::Constrained => Some(constrained_major),
     UpdateFunc::Sine => Some(sine_major),
     UpdateFunc::Pluck => Some(pluck_major),
+    UpdateFunc::Step => Some(step_major),
+    UpdateFunc::Ramp => Some(ramp_major),
+    UpdateFunc::Square => Some(square_major),
+    UpdateFunc::Triangle => Some(triangle_major),
+    UpdateFunc::Chirp => Some(chirp_major),
+    UpdateFunc::MultiSine => Some(multisine_major),
+    UpdateFunc::Table => Some(table_major),
+    UpdateFunc::DispRamp => Some(disp_ramp_major),
+    UpdateFunc::DispSine => Some(disp_sine_major),
+    UpdateFunc::DispTable => Some(disp_table_major),
+    UpdateFunc::Shear => Some(shear_major),
+    UpdateFunc::Compress => Some(compress_major),
+    UpdateFunc::Biaxial => Some(biaxial_major),
+    UpdateFunc::Equibiaxial => Some(equibiaxial_major),
   }
 }
 
@@ -43,6 +99,20 @@ pub fn enum_minor(e: &UpdateFunc) -> Option<generation::Hook> {
     UpdateFunc::Constrained => None,
     UpdateFunc::Sine => None,
     UpdateFunc::Pluck => None,
+    UpdateFunc::Step => None,
+    UpdateFunc::Ramp => None,
+    UpdateFunc::Square => None,
+    UpdateFunc::Triangle => None,
+    UpdateFunc::Chirp => None,
+    UpdateFunc::MultiSine => None,
+    UpdateFunc::Table => None,
+    UpdateFunc::DispRamp => None,
+    UpdateFunc::DispSine => None,
+    UpdateFunc::DispTable => None,
+    UpdateFunc::Shear => None,
+    UpdateFunc::Compress => None,
+    UpdateFunc::Biaxial => None,
+    UpdateFunc::Equibiaxial => None,
   }
 }
 
@@ -62,6 +132,94 @@ pub fn sine_major(_i: usize, j: usize, c: &mut cell::Cell, s: &settings::Setting
   }
 }
 
+// Drives the first column with force and fixes the last, like sine_major
+fn driven_major(j: usize, c: &mut cell::Cell, s: &settings::Settings, force: forces::ForceFunc) {
+  if j == s.nrows - 1 {
+    c.fixed = true;
+  } else if j == 0 {
+    c.force = force;
+  }
+}
+
+pub fn step_major(_i: usize, j: usize, c: &mut cell::Cell, s: &settings::Settings) {
+  driven_major(j, c, s, forces::ForceFunc::Step);
+}
+
+pub fn ramp_major(_i: usize, j: usize, c: &mut cell::Cell, s: &settings::Settings) {
+  driven_major(j, c, s, forces::ForceFunc::Ramp);
+}
+
+pub fn square_major(_i: usize, j: usize, c: &mut cell::Cell, s: &settings::Settings) {
+  driven_major(j, c, s, forces::ForceFunc::Square);
+}
+
+pub fn triangle_major(_i: usize, j: usize, c: &mut cell::Cell, s: &settings::Settings) {
+  driven_major(j, c, s, forces::ForceFunc::Triangle);
+}
+
+pub fn chirp_major(_i: usize, j: usize, c: &mut cell::Cell, s: &settings::Settings) {
+  driven_major(j, c, s, forces::ForceFunc::Chirp);
+}
+
+pub fn multisine_major(_i: usize, j: usize, c: &mut cell::Cell, s: &settings::Settings) {
+  driven_major(j, c, s, forces::ForceFunc::MultiSine);
+}
+
+pub fn table_major(_i: usize, j: usize, c: &mut cell::Cell, s: &settings::Settings) {
+  driven_major(j, c, s, forces::ForceFunc::Table);
+}
+
+// Moves the first column along a prescribed path and fixes the last
+fn displaced_major(j: usize, c: &mut cell::Cell, s: &settings::Settings, displace: forces::DispFunc) {
+  if j == s.nrows - 1 {
+    c.fixed = true;
+  } else if j == 0 {
+    c.displace = displace;
+  }
+}
+
+pub fn disp_ramp_major(_i: usize, j: usize, c: &mut cell::Cell, s: &settings::Settings) {
+  displaced_major(j, c, s, forces::DispFunc::Ramp);
+}
+
+pub fn disp_sine_major(_i: usize, j: usize, c: &mut cell::Cell, s: &settings::Settings) {
+  displaced_major(j, c, s, forces::DispFunc::Sine);
+}
+
+pub fn disp_table_major(_i: usize, j: usize, c: &mut cell::Cell, s: &settings::Settings) {
+  displaced_major(j, c, s, forces::DispFunc::Table);
+}
+
+// Drives the top row with force and fixes the bottom row
+fn plate_major(i: usize, c: &mut cell::Cell, s: &settings::Settings, force: forces::ForceFunc) {
+  if i == 0 {
+    c.fixed = true;
+  } else if i == s.nrows - 1 {
+    c.force = force;
+  }
+}
+
+pub fn shear_major(i: usize, _j: usize, c: &mut cell::Cell, s: &settings::Settings) {
+  plate_major(i, c, s, forces::ForceFunc::Shear);
+}
+
+pub fn compress_major(i: usize, _j: usize, c: &mut cell::Cell, s: &settings::Settings) {
+  plate_major(i, c, s, forces::ForceFunc::Compress);
+}
+
+// Drives every edge of the grid, nothing is fixed
+pub fn biaxial_major(i: usize, j: usize, c: &mut cell::Cell, s: &settings::Settings) {
+  if i == 0 || j == 0 || i == s.nrows - 1 || j == s.nrows - 1 {
+    c.force = forces::ForceFunc::Biaxial;
+  }
+}
+
+pub fn equibiaxial_major(i: usize, j: usize, c: &mut cell::Cell, s: &settings::Settings) {
+  if i == 0 || j == 0 || i == s.nrows - 1 || j == s.nrows - 1 {
+    c.force = forces::ForceFunc::Equibiaxial;
+  }
+}
+
 pub fn pluck_major(i: usize, j: usize, c: &mut cell::Cell, _s: &settings::Settings) {
   if i == 0 && j == 0 {
     c.update = UpdateFunc::Pluck;